@@ -0,0 +1,449 @@
+// 应用配置
+//
+// 按以下顺序加载, 后者覆盖前者:
+// 1. 内置默认值
+// 2. TOML配置文件, 路径由FPGA_RESERVE_CONFIG指定, 默认为fpga_reserve.toml(不存在时跳过)
+// 3. FPGA_RESERVE_<节>_<键>环境变量, 例如FPGA_RESERVE_DATABASE_URL, FPGA_RESERVE_SERVER_LISTEN_ADDR
+//
+// 环境变量的值按TOML值解析, 解析失败时视为字符串.
+
+use std::{net::SocketAddr, path::Path};
+
+use anyhow::{bail, Context, Result};
+use chrono::FixedOffset;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    db::token::{ACCESS_TOKEN_LIFETIME_SECS, REFRESH_TOKEN_LIFETIME_SECS},
+    keys::{STUDENT_KEYS_ENV, TEACHER_KEYS_ENV},
+};
+
+pub const CONFIG_PATH_ENV: &str = "FPGA_RESERVE_CONFIG";
+pub const DEFAULT_CONFIG_PATH: &str = "fpga_reserve.toml";
+const ENV_PREFIX: &str = "FPGA_RESERVE_";
+const SECTIONS: [&str; 6] = [
+    "database",
+    "server",
+    "jwt",
+    "argon2",
+    "login",
+    "reservation",
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub database: DatabaseConfig,
+    pub server: ServerConfig,
+    pub jwt: JwtConfig,
+    pub argon2: Argon2Config,
+    pub login: LoginConfig,
+    pub reservation: ReservationConfig,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    // 连接池大小
+    pub max_connections: u32,
+    pub min_connections: u32,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite://fpga_reserve.db?mode=rwc".to_string(),
+            max_connections: 10,
+            min_connections: 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub listen_addr: String,
+    // 允许跨域访问的来源, "*"表示任意来源, 为空表示不允许跨域
+    #[serde(deserialize_with = "string_or_list")]
+    pub cors_origins: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: "0.0.0.0:3000".to_string(),
+            cors_origins: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JwtConfig {
+    pub access_token_lifetime_secs: i64,
+    pub refresh_token_lifetime_secs: i64,
+    // 密钥描述, 格式见keys::KeyConfig
+    pub student_keys: Option<String>,
+    pub teacher_keys: Option<String>,
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            access_token_lifetime_secs: ACCESS_TOKEN_LIFETIME_SECS,
+            refresh_token_lifetime_secs: REFRESH_TOKEN_LIFETIME_SECS,
+            student_keys: None,
+            teacher_keys: None,
+        }
+    }
+}
+
+// 默认值与argon2::Params::DEFAULT一致
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Argon2Config {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for Argon2Config {
+    fn default() -> Self {
+        Self {
+            memory_kib: argon2::Params::DEFAULT_M_COST,
+            iterations: argon2::Params::DEFAULT_T_COST,
+            parallelism: argon2::Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl Argon2Config {
+    pub fn params(&self) -> Result<argon2::Params> {
+        argon2::Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| anyhow::anyhow!("argon2 parameters are invalid: {}", e))
+    }
+}
+
+// 登录失败限制
+// 连续失败超过允许次数后, 每次失败后的等待时间按指数增长, 达到锁定阈值后锁定一段时间
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoginConfig {
+    // 单个账号允许的连续失败次数, 超过后开始退避
+    pub account_free_attempts: i32,
+    // 单个账号的锁定阈值
+    pub account_lockout_threshold: i32,
+    // 单个IP允许的连续失败次数
+    pub ip_free_attempts: i32,
+    // 单个IP的锁定阈值
+    pub ip_lockout_threshold: i32,
+    // 第一次退避的等待时间
+    pub backoff_base_secs: i64,
+    // 退避等待时间的上限
+    pub max_backoff_secs: i64,
+    // 锁定时长
+    pub lockout_secs: i64,
+    // 距上次失败超过该时间后重新计数
+    pub failure_window_secs: i64,
+    // 部署在反向代理之后时, 从X-Forwarded-For获取客户端IP
+    pub trust_proxy_headers: bool,
+}
+
+impl Default for LoginConfig {
+    fn default() -> Self {
+        Self {
+            account_free_attempts: 3,
+            account_lockout_threshold: 10,
+            ip_free_attempts: 20,
+            ip_lockout_threshold: 100,
+            backoff_base_secs: 1,
+            max_backoff_secs: 300,
+            lockout_secs: 15 * 60,
+            failure_window_secs: 60 * 60,
+            trust_proxy_headers: false,
+        }
+    }
+}
+
+// 预约规则的默认值, 实验可以单独覆盖
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReservationConfig {
+    // 新建时间段的默认人数上限
+    pub default_capacity: i32,
+    // 开始前多少小时之前可以免费取消
+    pub free_cancel_hours: i64,
+    // 逾期取消记录的违约次数
+    pub late_cancel_strikes: i32,
+    // 缺席记录的违约次数
+    pub no_show_strikes: i32,
+    // 违约次数达到该值后暂停预约资格
+    pub strike_threshold: i32,
+    // 暂停预约资格的天数
+    pub suspension_days: i64,
+    // 实验室所在时区相对UTC的偏移(分钟), 用于按当地日期和时刻生成时间段
+    pub utc_offset_minutes: i32,
+}
+
+impl Default for ReservationConfig {
+    fn default() -> Self {
+        Self {
+            default_capacity: 30,
+            free_cancel_hours: 24,
+            late_cancel_strikes: 1,
+            no_show_strikes: 2,
+            strike_threshold: 3,
+            suspension_days: 14,
+            utc_offset_minutes: 8 * 60,
+        }
+    }
+}
+
+impl ReservationConfig {
+    pub fn timezone(&self) -> FixedOffset {
+        FixedOffset::east_opt(self.utc_offset_minutes * 60).expect("validated utc offset")
+    }
+}
+
+// 支持TOML数组和逗号分隔的字符串两种写法, 方便通过环境变量设置
+fn string_or_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        String(String),
+        List(Vec<String>),
+    }
+
+    Ok(match StringOrList::deserialize(deserializer)? {
+        StringOrList::String(value) => value
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .collect(),
+        StringOrList::List(values) => values,
+    })
+}
+
+// 把环境变量的值解析为TOML值
+fn parse_env_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+impl Config {
+    // 从默认路径的配置文件和进程环境变量加载
+    pub fn load() -> Result<Self> {
+        let path = std::env::var(CONFIG_PATH_ENV).ok();
+        let file = match &path {
+            Some(path) => Some(
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read config file {}", path))?,
+            ),
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Some(
+                std::fs::read_to_string(DEFAULT_CONFIG_PATH).with_context(|| {
+                    format!("Failed to read config file {}", DEFAULT_CONFIG_PATH)
+                })?,
+            ),
+            None => None,
+        };
+        Self::from_sources(file.as_deref(), std::env::vars())
+    }
+
+    pub fn from_sources(
+        file: Option<&str>,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let mut table = match file {
+            Some(file) => toml::from_str::<toml::Table>(file).context("Invalid config file")?,
+            None => toml::Table::new(),
+        };
+
+        for (key, value) in env {
+            // 兼容单独设置密钥的环境变量
+            let key = match key.as_str() {
+                STUDENT_KEYS_ENV => "FPGA_RESERVE_JWT_STUDENT_KEYS".to_string(),
+                TEACHER_KEYS_ENV => "FPGA_RESERVE_JWT_TEACHER_KEYS".to_string(),
+                _ => key,
+            };
+            let Some(name) = key.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let name = name.to_lowercase();
+            let Some((section, field)) = SECTIONS.iter().find_map(|section| {
+                name.strip_prefix(section)
+                    .and_then(|rest| rest.strip_prefix('_'))
+                    .map(|field| (*section, field))
+            }) else {
+                continue;
+            };
+            let Some(fields) = table
+                .entry(section)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+            else {
+                bail!("Config section [{}] must be a table", section);
+            };
+            fields.insert(field.to_string(), parse_env_value(&value));
+        }
+
+        let config: Config = toml::Value::Table(table)
+            .try_into()
+            .context("Invalid configuration")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // 启动时检查配置, 给出具体的错误原因
+    pub fn validate(&self) -> Result<()> {
+        if self.database.url.trim().is_empty() {
+            bail!("database.url must not be empty");
+        }
+        if self.database.max_connections == 0 {
+            bail!("database.max_connections must be positive");
+        }
+        if self.database.min_connections > self.database.max_connections {
+            bail!("database.min_connections must not exceed database.max_connections");
+        }
+        self.server
+            .listen_addr
+            .parse::<SocketAddr>()
+            .with_context(|| {
+                format!(
+                    "server.listen_addr {:?} is invalid",
+                    self.server.listen_addr
+                )
+            })?;
+        for origin in &self.server.cors_origins {
+            if origin != "*" && !(origin.starts_with("http://") || origin.starts_with("https://")) {
+                bail!(
+                    "server.cors_origins entry {:?} must be \"*\" or an http(s) origin",
+                    origin
+                );
+            }
+        }
+        if self.jwt.access_token_lifetime_secs <= 0 {
+            bail!("jwt.access_token_lifetime_secs must be positive");
+        }
+        if self.jwt.refresh_token_lifetime_secs <= self.jwt.access_token_lifetime_secs {
+            bail!("jwt.refresh_token_lifetime_secs must be longer than the access token lifetime");
+        }
+        self.argon2.params()?;
+        let login = &self.login;
+        if login.account_free_attempts < 0 || login.ip_free_attempts < 0 {
+            bail!("login free attempts must not be negative");
+        }
+        if login.account_lockout_threshold <= login.account_free_attempts {
+            bail!("login.account_lockout_threshold must exceed login.account_free_attempts");
+        }
+        if login.ip_lockout_threshold <= login.ip_free_attempts {
+            bail!("login.ip_lockout_threshold must exceed login.ip_free_attempts");
+        }
+        if login.backoff_base_secs <= 0 || login.max_backoff_secs < login.backoff_base_secs {
+            bail!("login.max_backoff_secs must be at least login.backoff_base_secs, which must be positive");
+        }
+        if login.lockout_secs <= 0 || login.failure_window_secs <= 0 {
+            bail!("login.lockout_secs and login.failure_window_secs must be positive");
+        }
+        let reservation = &self.reservation;
+        if reservation.default_capacity <= 0 {
+            bail!("reservation.default_capacity must be positive");
+        }
+        if reservation.free_cancel_hours < 0 {
+            bail!("reservation.free_cancel_hours must not be negative");
+        }
+        if reservation.late_cancel_strikes < 0 || reservation.no_show_strikes < 0 {
+            bail!("reservation strike counts must not be negative");
+        }
+        if reservation.strike_threshold <= 0 {
+            bail!("reservation.strike_threshold must be positive");
+        }
+        if reservation.suspension_days < 0 {
+            bail!("reservation.suspension_days must not be negative");
+        }
+        if reservation.utc_offset_minutes.abs() >= 24 * 60 {
+            bail!("reservation.utc_offset_minutes must be within one day");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_defaults_are_valid() {
+        let config = Config::from_sources(None, Vec::new()).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_file_and_env_overrides() {
+        let file = r#"
+            [database]
+            url = "mysql://localhost/fpga"
+            max_connections = 20
+
+            [server]
+            cors_origins = ["https://lab.example.edu"]
+        "#;
+        let config = Config::from_sources(
+            Some(file),
+            env(&[
+                ("FPGA_RESERVE_DATABASE_MAX_CONNECTIONS", "5"),
+                ("FPGA_RESERVE_SERVER_LISTEN_ADDR", "127.0.0.1:8080"),
+                (
+                    "FPGA_RESERVE_SERVER_CORS_ORIGINS",
+                    "https://a.example.edu, https://b.example.edu",
+                ),
+                ("FPGA_RESERVE_STUDENT_KEYS", "main=env:STUDENT_SECRET"),
+                ("FPGA_RESERVE_CONFIG", "ignored.toml"),
+                ("HOME", "/root"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.database.url, "mysql://localhost/fpga");
+        assert_eq!(config.database.max_connections, 5);
+        assert_eq!(config.server.listen_addr, "127.0.0.1:8080");
+        assert_eq!(
+            config.server.cors_origins,
+            vec!["https://a.example.edu", "https://b.example.edu"]
+        );
+        assert_eq!(
+            config.jwt.student_keys.as_deref(),
+            Some("main=env:STUDENT_SECRET")
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for vars in [
+            [("FPGA_RESERVE_DATABASE_MAX_CONNECTIONS", "0")],
+            [("FPGA_RESERVE_SERVER_LISTEN_ADDR", "localhost")],
+            [("FPGA_RESERVE_JWT_ACCESS_TOKEN_LIFETIME_SECS", "-1")],
+            [("FPGA_RESERVE_ARGON2_MEMORY_KIB", "1")],
+            [("FPGA_RESERVE_RESERVATION_STRIKE_THRESHOLD", "0")],
+            [("FPGA_RESERVE_DATABASE_MAX_CONNECTIONS", "many")],
+        ] {
+            assert!(
+                Config::from_sources(None, env(&vars)).is_err(),
+                "{:?}",
+                vars
+            );
+        }
+        assert!(Config::from_sources(Some("[database]\nuri = \"x\""), Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use std::net::SocketAddr;
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{header::AUTHORIZATION, request::Parts},
+};
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::LoginConfig,
+    db::{
+        models::{
+            class_student_junction,
+            login_throttle::{self, ThrottleScope},
+            student, teacher,
+        },
+        rbac::{Permission, Subject},
+        token::decode_access_token,
+    },
+    error::{Error, Result},
+    state::AppState,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoginRequest {
+    // 学号/工号或账号
+    pub account: String,
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub old_password: String,
+    pub new_password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub new_password: String,
+}
+
+// 客户端IP, 用于登录失败限制
+#[derive(Clone, Debug)]
+pub struct ClientIp(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let state = AppState::from_ref(state);
+        // 只有部署在可信的反向代理之后时才使用X-Forwarded-For, 否则可以被客户端伪造
+        if state.config.login.trust_proxy_headers {
+            if let Some(ip) = parts
+                .headers
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .map(str::trim)
+                .filter(|ip| !ip.is_empty())
+            {
+                return Ok(ClientIp(ip.to_string()));
+            }
+        }
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        Ok(ClientIp(ip))
+    }
+}
+
+// 登录前检查账号和IP是否处于等待或锁定期间
+pub async fn check_login_throttle_with_db<C>(
+    scope: ThrottleScope,
+    account: &str,
+    ip: &ClientIp,
+    db: &C,
+) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    login_throttle::Model::check_with_db(scope, account, db).await?;
+    login_throttle::Model::check_with_db(ThrottleScope::Ip, &ip.0, db).await
+}
+
+// 根据登录结果更新失败记录, 只有账号或密码错误才计入失败
+pub async fn record_login_result_with_db<T, C>(
+    result: Result<T>,
+    scope: ThrottleScope,
+    account: &str,
+    ip: &ClientIp,
+    config: &LoginConfig,
+    db: &C,
+) -> Result<T>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    match &result {
+        Ok(_) => login_throttle::Model::clear_with_db(scope, account, db).await?,
+        Err(Error::InvalidCredentials) => {
+            login_throttle::Model::record_failure_with_db(scope, account, config, db).await?;
+            login_throttle::Model::record_failure_with_db(ThrottleScope::Ip, &ip.0, config, db)
+                .await?;
+        }
+        Err(_) => {}
+    }
+    result
+}
+
+fn bearer_token(parts: &Parts) -> Result<&str> {
+    parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(Error::Unauthorized("Missing bearer token".to_string()))
+}
+
+// 持有有效访问令牌的学生, 值为学生主键, 不检查是否需要修改密码
+// 只用于修改密码等必须在强制改密期间可用的接口
+#[derive(Clone, Copy, Debug)]
+pub struct StudentSession(pub i64);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for StudentSession
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let state = AppState::from_ref(state);
+        let claims = decode_access_token(bearer_token(parts)?, &state.keys.student)?;
+        Ok(StudentSession(claims.sub))
+    }
+}
+
+// 已登录且不需要修改密码的学生, 值为学生主键
+#[derive(Clone, Copy, Debug)]
+pub struct AuthStudent(pub i64);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthStudent
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let StudentSession(student_pid) = StudentSession::from_request_parts(parts, state).await?;
+        let state = AppState::from_ref(state);
+        let student = student::Entity::find_by_id(student_pid)
+            .one(&state.db)
+            .await?
+            .ok_or(Error::Unauthorized("Student no longer exists".to_string()))?;
+        if student.must_change_password {
+            return Err(Error::PasswordChangeRequired);
+        }
+        Ok(AuthStudent(student_pid))
+    }
+}
+
+// 持有有效访问令牌的教师, 值为教师主键, 不检查是否需要修改密码
+#[derive(Clone, Copy, Debug)]
+pub struct TeacherSession(pub i64);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for TeacherSession
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let state = AppState::from_ref(state);
+        let claims = decode_access_token(bearer_token(parts)?, &state.keys.teacher)?;
+        Ok(TeacherSession(claims.sub))
+    }
+}
+
+// 已登录且不需要修改密码的教师, 值为教师主键
+#[derive(Clone, Copy, Debug)]
+pub struct AuthTeacher(pub i64);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthTeacher
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let TeacherSession(teacher_pid) = TeacherSession::from_request_parts(parts, state).await?;
+        let state = AppState::from_ref(state);
+        let teacher = AuthTeacher(teacher_pid).find_with_db(&state.db).await?;
+        if teacher.must_change_password {
+            return Err(Error::PasswordChangeRequired);
+        }
+        Ok(AuthTeacher(teacher_pid))
+    }
+}
+
+// 已登录的教师或学生, 用于双方都可以查看的只读接口
+#[derive(Clone, Copy, Debug)]
+pub enum AuthUser {
+    Teacher(i64),
+    Student(i64),
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthUser
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        // 令牌能用教师密钥验证时按教师处理, 否则按学生处理
+        if TeacherSession::from_request_parts(parts, state)
+            .await
+            .is_ok()
+        {
+            let AuthTeacher(teacher_pid) = AuthTeacher::from_request_parts(parts, state).await?;
+            return Ok(AuthUser::Teacher(teacher_pid));
+        }
+        let AuthStudent(student_pid) = AuthStudent::from_request_parts(parts, state).await?;
+        Ok(AuthUser::Student(student_pid))
+    }
+}
+
+impl AuthTeacher {
+    pub async fn find_with_db<C>(&self, db: &C) -> Result<teacher::Model>
+    where
+        C: ConnectionTrait,
+    {
+        teacher::Entity::find_by_id(self.0)
+            .one(db)
+            .await?
+            .ok_or(Error::Unauthorized("Teacher no longer exists".to_string()))
+    }
+
+    // 按教师当前的全局角色检查权限
+    pub async fn require_with_db<C>(&self, permission: Permission, db: &C) -> Result<teacher::Model>
+    where
+        C: ConnectionTrait,
+    {
+        let teacher = self.find_with_db(db).await?;
+        Subject::teacher(&teacher).require(permission)?;
+        Ok(teacher)
+    }
+
+    // 针对某个学生的操作: 拥有全局权限, 或在该学生所在的任一班级中拥有权限
+    pub async fn require_for_student_with_db<C>(
+        &self,
+        student_pid: i64,
+        permission: Permission,
+        db: &C,
+    ) -> Result<teacher::Model>
+    where
+        C: ConnectionTrait,
+    {
+        let teacher = self.find_with_db(db).await?;
+        if Subject::teacher(&teacher).can(permission) {
+            return Ok(teacher);
+        }
+        let memberships = class_student_junction::Entity::find()
+            .filter(class_student_junction::Column::StudentPid.eq(student_pid))
+            .all(db)
+            .await?;
+        for membership in memberships {
+            let subject =
+                Subject::teacher_in_class_with_db(&teacher, membership.class_pid, db).await?;
+            if subject.can(permission) {
+                return Ok(teacher);
+            }
+        }
+        Err(Error::Forbidden(format!(
+            "Missing permission {:?}",
+            permission
+        )))
+    }
+
+    // 按教师是否负责指定实验检查权限
+    pub async fn require_for_experiment_with_db<C>(
+        &self,
+        experiment_pid: i64,
+        permission: Permission,
+        db: &C,
+    ) -> Result<teacher::Model>
+    where
+        C: ConnectionTrait,
+    {
+        let teacher = self.find_with_db(db).await?;
+        Subject::teacher_for_experiment_with_db(&teacher, experiment_pid, db)
+            .await?
+            .require(permission)?;
+        Ok(teacher)
+    }
+
+    // 按教师在指定班级中的身份检查权限, 班级管理员拥有额外的班级管理权限
+    pub async fn require_in_class_with_db<C>(
+        &self,
+        class_pid: i64,
+        permission: Permission,
+        db: &C,
+    ) -> Result<teacher::Model>
+    where
+        C: ConnectionTrait,
+    {
+        let teacher = self.find_with_db(db).await?;
+        Subject::teacher_in_class_with_db(&teacher, class_pid, db)
+            .await?
+            .require(permission)?;
+        Ok(teacher)
+    }
+}
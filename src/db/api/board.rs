@@ -0,0 +1,96 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, patch},
+    Json, Router,
+};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{
+        models::board::{self, BoardStatus},
+        rbac::Permission,
+    },
+    error::{Error, Result},
+    state::AppState,
+};
+
+use super::auth::AuthTeacher;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateBoard {
+    pub model: String,
+    pub serial: String,
+    pub location: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct UpdateBoard {
+    pub location: Option<String>,
+    pub status: Option<BoardStatus>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BoardFilter {
+    pub status: Option<BoardStatus>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_boards).post(create_board))
+        .route("/:id", patch(update_board))
+}
+
+async fn list_boards(
+    State(state): State<AppState>,
+    _teacher: AuthTeacher,
+    Query(filter): Query<BoardFilter>,
+) -> Result<Json<Vec<board::Model>>> {
+    let mut query = board::Entity::find().order_by_asc(board::Column::Id);
+    if let Some(status) = filter.status {
+        query = query.filter(board::Column::Status.eq(status));
+    }
+    Ok(Json(query.all(&state.db).await?))
+}
+
+async fn create_board(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Json(body): Json<CreateBoard>,
+) -> Result<Json<board::Model>> {
+    teacher
+        .require_with_db(Permission::ManageBoards, &state.db)
+        .await?;
+    if body.model.trim().is_empty() || body.serial.trim().is_empty() {
+        return Err(Error::BadRequest(
+            "Board model and serial must not be empty".to_string(),
+        ));
+    }
+    let board = board::ActiveModel::new(body.model, body.serial, body.location)
+        .insert(&state.db)
+        .await?;
+    Ok(Json(board))
+}
+
+async fn update_board(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(body): Json<UpdateBoard>,
+) -> Result<Json<board::Model>> {
+    teacher
+        .require_with_db(Permission::ManageBoards, &state.db)
+        .await?;
+    let board = board::Entity::find_by_id(id)
+        .one(&state.db)
+        .await?
+        .ok_or(Error::not_found("Board"))?;
+    let mut board: board::ActiveModel = board.into();
+    if let Some(location) = body.location {
+        board.location = Set(Some(location));
+    }
+    if let Some(status) = body.status {
+        board.status = Set(status);
+    }
+    Ok(Json(board.update(&state.db).await?))
+}
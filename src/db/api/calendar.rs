@@ -0,0 +1,253 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use chrono::{NaiveDate, Utc};
+use sea_orm::{ActiveModelTrait, EntityTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{
+        ical::{render_calendar, CalendarEvent},
+        models::{
+            blackout_date,
+            calendar_feed::{self, FeedOwner},
+            term::{self, WeekLabel},
+        },
+        rbac::Permission,
+        services::calendar::{student_events_with_db, teacher_events_with_db},
+    },
+    error::{Error, Result},
+    state::AppState,
+};
+
+use super::auth::{AuthStudent, AuthTeacher};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateTerm {
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateBlackout {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DateRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LabelQuery {
+    // 为空时使用当天
+    pub date: Option<NaiveDate>,
+}
+
+// 某一天的周次和是否开放预约
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DayInfo {
+    pub date: NaiveDate,
+    // 不在任何学期内时为空
+    pub week: Option<WeekLabel>,
+    pub blackouts: Vec<blackout_date::Model>,
+}
+
+// 新生成的订阅地址, 令牌只在此时返回一次
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeedInfo {
+    pub url: String,
+}
+
+impl FeedInfo {
+    fn new(token: &str) -> Self {
+        Self {
+            url: format!("/api/v1/calendar/feeds/{}.ics", token),
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/terms", get(list_terms).post(create_term))
+        .route("/terms/:id", delete(delete_term))
+        .route("/blackouts", get(list_blackouts).post(create_blackout))
+        .route("/blackouts/:id", delete(delete_blackout))
+        .route("/label", get(get_label))
+        .route(
+            "/students/feed",
+            post(regenerate_student_feed).delete(revoke_student_feed),
+        )
+        .route(
+            "/teachers/feed",
+            post(regenerate_teacher_feed).delete(revoke_teacher_feed),
+        )
+        .route("/feeds/:file", get(get_feed))
+}
+
+// 返回.ics文件, filename不为空时作为附件下载
+pub fn ics_response(name: &str, events: &[CalendarEvent], filename: Option<&str>) -> Response {
+    let body = render_calendar(name, events);
+    match filename {
+        Some(filename) => (
+            [
+                (CONTENT_TYPE, "text/calendar; charset=utf-8".to_string()),
+                (
+                    CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", filename),
+                ),
+            ],
+            body,
+        )
+            .into_response(),
+        None => ([(CONTENT_TYPE, "text/calendar; charset=utf-8")], body).into_response(),
+    }
+}
+
+async fn regenerate_student_feed(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+) -> Result<Json<FeedInfo>> {
+    let (token, _) =
+        calendar_feed::Model::regenerate_with_db(FeedOwner::Student, student_pid, &state.db)
+            .await?;
+    Ok(Json(FeedInfo::new(&token)))
+}
+
+async fn revoke_student_feed(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+) -> Result<()> {
+    calendar_feed::Model::revoke_with_db(FeedOwner::Student, student_pid, &state.db).await
+}
+
+async fn regenerate_teacher_feed(
+    State(state): State<AppState>,
+    AuthTeacher(teacher_pid): AuthTeacher,
+) -> Result<Json<FeedInfo>> {
+    let (token, _) =
+        calendar_feed::Model::regenerate_with_db(FeedOwner::Teacher, teacher_pid, &state.db)
+            .await?;
+    Ok(Json(FeedInfo::new(&token)))
+}
+
+async fn revoke_teacher_feed(
+    State(state): State<AppState>,
+    AuthTeacher(teacher_pid): AuthTeacher,
+) -> Result<()> {
+    calendar_feed::Model::revoke_with_db(FeedOwner::Teacher, teacher_pid, &state.db).await
+}
+
+// 日历客户端订阅的地址, 不需要登录, 凭令牌只读访问
+async fn get_feed(State(state): State<AppState>, Path(file): Path<String>) -> Result<Response> {
+    let token = file.strip_suffix(".ics").unwrap_or(&file);
+    let feed = calendar_feed::Model::find_by_token_with_db(token, &state.db).await?;
+    let events = match feed.owner {
+        FeedOwner::Student => student_events_with_db(feed.owner_pid, &state.db).await?,
+        FeedOwner::Teacher => teacher_events_with_db(feed.owner_pid, &state.db).await?,
+    };
+    Ok(ics_response("实验预约", &events, None))
+}
+
+async fn list_terms(State(state): State<AppState>) -> Result<Json<Vec<term::Model>>> {
+    Ok(Json(term::Model::list_with_db(&state.db).await?))
+}
+
+async fn create_term(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Json(body): Json<CreateTerm>,
+) -> Result<Json<term::Model>> {
+    teacher
+        .require_with_db(Permission::ManageCalendar, &state.db)
+        .await?;
+    let term = term::ActiveModel::new_with_db(body.name, body.start_date, body.end_date, &state.db)
+        .await?
+        .insert(&state.db)
+        .await?;
+    Ok(Json(term))
+}
+
+async fn delete_term(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    teacher
+        .require_with_db(Permission::ManageCalendar, &state.db)
+        .await?;
+    let deleted = term::Entity::delete_by_id(id).exec(&state.db).await?;
+    if deleted.rows_affected == 0 {
+        return Err(Error::not_found("Term"));
+    }
+    Ok(())
+}
+
+async fn list_blackouts(
+    State(state): State<AppState>,
+    Query(range): Query<DateRange>,
+) -> Result<Json<Vec<blackout_date::Model>>> {
+    Ok(Json(
+        blackout_date::Model::list_between_with_db(range.from, range.to, &state.db).await?,
+    ))
+}
+
+async fn create_blackout(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Json(body): Json<CreateBlackout>,
+) -> Result<Json<blackout_date::Model>> {
+    teacher
+        .require_with_db(Permission::ManageCalendar, &state.db)
+        .await?;
+    let blackout = blackout_date::ActiveModel::new(body.start_date, body.end_date, body.reason)?
+        .insert(&state.db)
+        .await?;
+    Ok(Json(blackout))
+}
+
+async fn delete_blackout(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    teacher
+        .require_with_db(Permission::ManageCalendar, &state.db)
+        .await?;
+    let deleted = blackout_date::Entity::delete_by_id(id)
+        .exec(&state.db)
+        .await?;
+    if deleted.rows_affected == 0 {
+        return Err(Error::not_found("Blackout date"));
+    }
+    Ok(())
+}
+
+// "第7周 星期二"形式的日期标签
+async fn get_label(
+    State(state): State<AppState>,
+    Query(query): Query<LabelQuery>,
+) -> Result<Json<DayInfo>> {
+    let date = query.date.unwrap_or_else(|| {
+        Utc::now()
+            .with_timezone(&state.config.reservation.timezone())
+            .date_naive()
+    });
+    let week = term::Model::find_by_date_with_db(date, &state.db)
+        .await?
+        .and_then(|term| term.label(date));
+    let blackouts = blackout_date::Model::list_between_with_db(date, date, &state.db).await?;
+    Ok(Json(DayInfo {
+        date,
+        week,
+        blackouts,
+    }))
+}
@@ -0,0 +1,241 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use sea_orm::{ConnectionTrait, EntityTrait, ModelTrait, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{
+        models::{class, student, teacher},
+        rbac::{Permission, Subject},
+        services,
+    },
+    error::{Error, Result},
+    state::AppState,
+};
+
+use super::{auth::AuthTeacher, student::StudentInfo, teacher::TeacherInfo};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateClass {
+    pub class_id: Option<String>,
+    // 初始学生名单
+    #[serde(default)]
+    pub students: Vec<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransferStudent {
+    pub to_class_pid: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddStudent {
+    pub student_pid: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddTeacher {
+    pub teacher_pid: i64,
+    // 是否同时设为班级管理员
+    #[serde(default)]
+    pub admin: bool,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_classes).post(create_class))
+        .route("/:id", get(get_class).delete(delete_class))
+        .route(
+            "/:id/students",
+            get(get_class_students).post(add_class_student),
+        )
+        .route("/:id/students/:student_pid", delete(remove_class_student))
+        .route(
+            "/:id/students/:student_pid/transfer",
+            post(transfer_class_student),
+        )
+        .route(
+            "/:id/teachers",
+            get(get_class_teachers).post(add_class_teacher),
+        )
+        .route("/:id/teachers/:teacher_pid", delete(remove_class_teacher))
+}
+
+async fn list_classes(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+) -> Result<Json<Vec<class::Model>>> {
+    teacher
+        .require_with_db(Permission::ViewClassRoster, &state.db)
+        .await?;
+    let classes = class::Entity::find().all(&state.db).await?;
+    Ok(Json(classes))
+}
+
+async fn create_class(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Json(body): Json<CreateClass>,
+) -> Result<Json<class::Model>> {
+    let teacher = teacher
+        .require_with_db(Permission::CreateClass, &state.db)
+        .await?;
+    // 任何教师都能创建班级, 直接拉入已有学生需要导入名单的全局权限
+    if !body.students.is_empty() {
+        Subject::teacher(&teacher).require(Permission::ImportRoster)?;
+    }
+    // 创建者自动成为班级管理员
+    let class =
+        services::class::create_class_with_db(body.class_id, teacher.id, &body.students, &state.db)
+            .await?;
+    Ok(Json(class))
+}
+
+async fn find_class<C>(id: i64, db: &C) -> Result<class::Model>
+where
+    C: ConnectionTrait,
+{
+    class::Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or(Error::not_found("Class"))
+}
+
+async fn get_class(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<class::Model>> {
+    let class = find_class(id, &state.db).await?;
+    teacher
+        .require_in_class_with_db(class.id, Permission::ViewClassRoster, &state.db)
+        .await?;
+    Ok(Json(class))
+}
+
+async fn delete_class(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    let class = find_class(id, &state.db).await?;
+    teacher
+        .require_in_class_with_db(class.id, Permission::DeleteClass, &state.db)
+        .await?;
+    class.delete(&state.db).await?;
+    Ok(())
+}
+
+async fn get_class_students(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<StudentInfo>>> {
+    let class = find_class(id, &state.db).await?;
+    teacher
+        .require_in_class_with_db(class.id, Permission::ViewClassRoster, &state.db)
+        .await?;
+    let students = class.find_related(student::Entity).all(&state.db).await?;
+    Ok(Json(students.into_iter().map(StudentInfo::from).collect()))
+}
+
+async fn get_class_teachers(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<TeacherInfo>>> {
+    let class = find_class(id, &state.db).await?;
+    teacher
+        .require_in_class_with_db(class.id, Permission::ViewClassRoster, &state.db)
+        .await?;
+    let teachers = class.find_related(teacher::Entity).all(&state.db).await?;
+    Ok(Json(teachers.into_iter().map(TeacherInfo::from).collect()))
+}
+
+async fn add_class_student(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(body): Json<AddStudent>,
+) -> Result<Json<StudentInfo>> {
+    let class = find_class(id, &state.db).await?;
+    // 班级管理员可以由自己创建班级得到, 按主键拉入任意学生需要导入名单的全局权限
+    teacher
+        .require_with_db(Permission::ImportRoster, &state.db)
+        .await?;
+    let student = student::Entity::find_by_id(body.student_pid)
+        .one(&state.db)
+        .await?
+        .ok_or(Error::not_found("Student"))?;
+    // 加入班级和自动选修班级实验需要同时成功
+    let txn = state.db.begin().await?;
+    student.join_class_with_db(class.id, &txn).await?;
+    txn.commit().await?;
+    Ok(Json(student.into()))
+}
+
+async fn remove_class_student(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path((id, student_pid)): Path<(i64, i64)>,
+) -> Result<()> {
+    teacher
+        .require_in_class_with_db(id, Permission::ManageClassStudents, &state.db)
+        .await?;
+    let student = student::Entity::find_by_id(student_pid)
+        .one(&state.db)
+        .await?
+        .ok_or(Error::not_found("Student"))?;
+    student.leave_class_with_db(id, &state.db).await
+}
+
+// 转班需要同时是原班级和目标班级的管理员
+async fn transfer_class_student(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path((id, student_pid)): Path<(i64, i64)>,
+    Json(body): Json<TransferStudent>,
+) -> Result<()> {
+    for class_pid in [id, body.to_class_pid] {
+        teacher
+            .require_in_class_with_db(class_pid, Permission::ManageClassStudents, &state.db)
+            .await?;
+    }
+    services::class::transfer_student_with_db(student_pid, id, body.to_class_pid, &state.db).await
+}
+
+async fn add_class_teacher(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(body): Json<AddTeacher>,
+) -> Result<Json<TeacherInfo>> {
+    let class = find_class(id, &state.db).await?;
+    auth.require_in_class_with_db(class.id, Permission::ManageClassTeachers, &state.db)
+        .await?;
+    let teacher = teacher::Entity::find_by_id(body.teacher_pid)
+        .one(&state.db)
+        .await?
+        .ok_or(Error::not_found("Teacher"))?;
+    teacher
+        .join_class_with_db(class.id, body.admin, &state.db)
+        .await?;
+    Ok(Json(teacher.into()))
+}
+
+async fn remove_class_teacher(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+    Path((id, teacher_pid)): Path<(i64, i64)>,
+) -> Result<()> {
+    auth.require_in_class_with_db(id, Permission::ManageClassTeachers, &state.db)
+        .await?;
+    let teacher = teacher::Entity::find_by_id(teacher_pid)
+        .one(&state.db)
+        .await?
+        .ok_or(Error::not_found("Teacher"))?;
+    teacher.leave_class_with_db(id, &state.db).await
+}
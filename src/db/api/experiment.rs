@@ -0,0 +1,378 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{delete, get, patch, post},
+    Json, Router,
+};
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{
+        models::{
+            blackout_date, class, class_teacher_junction,
+            experiment::{self, ExperimentFields},
+            experiment_time_ranges, teacher,
+            time_range_series::{self, Occurrence, RecurrenceRule},
+        },
+        rbac::{Permission, Subject},
+        services::{
+            self,
+            time_range_series::{GeneratedSeries, SeriesChange, SeriesUpdate},
+        },
+    },
+    error::{Error, Result},
+    state::AppState,
+};
+
+use super::{
+    auth::{AuthTeacher, AuthUser},
+    teacher::TeacherInfo,
+};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExperimentFilter {
+    // 是否包含已归档的实验
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AssignClass {
+    pub class_pid: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddOwner {
+    pub teacher_pid: i64,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_experiments).post(create_experiment))
+        .route("/mine", get(list_my_experiments))
+        .route("/:id", get(get_experiment).put(update_experiment))
+        .route(
+            "/:id/archive",
+            post(archive_experiment).delete(unarchive_experiment),
+        )
+        .route(
+            "/:id/teachers",
+            get(get_experiment_teachers).post(add_experiment_teacher),
+        )
+        .route(
+            "/:id/teachers/:teacher_pid",
+            delete(remove_experiment_teacher),
+        )
+        .route(
+            "/:id/classes",
+            get(get_experiment_classes).post(assign_experiment_class),
+        )
+        .route("/:id/classes/:class_pid", delete(unassign_experiment_class))
+        .route("/:id/time-ranges", get(get_experiment_time_ranges))
+        .route(
+            "/:id/series",
+            get(list_experiment_series).post(create_experiment_series),
+        )
+        .route("/:id/series/preview", post(preview_experiment_series))
+        .route(
+            "/:id/series/:series_pid",
+            patch(update_experiment_series).delete(delete_experiment_series),
+        )
+}
+
+async fn list_experiments(
+    State(state): State<AppState>,
+    _user: AuthUser,
+    Query(filter): Query<ExperimentFilter>,
+) -> Result<Json<Vec<experiment::Model>>> {
+    Ok(Json(
+        experiment::Model::list_with_db(filter.include_archived, &state.db).await?,
+    ))
+}
+
+// 当前教师负责的实验
+async fn list_my_experiments(
+    State(state): State<AppState>,
+    AuthTeacher(teacher_pid): AuthTeacher,
+    Query(filter): Query<ExperimentFilter>,
+) -> Result<Json<Vec<experiment::Model>>> {
+    Ok(Json(
+        experiment::Model::list_by_teacher_with_db(teacher_pid, filter.include_archived, &state.db)
+            .await?,
+    ))
+}
+
+async fn create_experiment(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Json(body): Json<ExperimentFields>,
+) -> Result<Json<experiment::Model>> {
+    let teacher = teacher
+        .require_with_db(Permission::CreateExperiment, &state.db)
+        .await?;
+    // 创建者自动成为负责教师
+    let experiment =
+        services::experiment::create_experiment_with_db(body, teacher.id, &state.db).await?;
+    Ok(Json(experiment))
+}
+
+async fn get_experiment(
+    State(state): State<AppState>,
+    _user: AuthUser,
+    Path(id): Path<i64>,
+) -> Result<Json<experiment::Model>> {
+    Ok(Json(
+        experiment::Model::find_by_id_with_db(id, &state.db).await?,
+    ))
+}
+
+async fn update_experiment(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(body): Json<ExperimentFields>,
+) -> Result<Json<experiment::Model>> {
+    let experiment = experiment::Model::find_by_id_with_db(id, &state.db).await?;
+    teacher
+        .require_for_experiment_with_db(experiment.id, Permission::ManageExperiment, &state.db)
+        .await?;
+    Ok(Json(experiment.update_with_db(body, &state.db).await?))
+}
+
+async fn set_archived(
+    state: AppState,
+    teacher: AuthTeacher,
+    id: i64,
+    archived: bool,
+) -> Result<Json<experiment::Model>> {
+    let experiment = experiment::Model::find_by_id_with_db(id, &state.db).await?;
+    teacher
+        .require_for_experiment_with_db(experiment.id, Permission::ManageExperiment, &state.db)
+        .await?;
+    Ok(Json(
+        experiment.set_archived_with_db(archived, &state.db).await?,
+    ))
+}
+
+async fn archive_experiment(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<experiment::Model>> {
+    set_archived(state, teacher, id, true).await
+}
+
+async fn unarchive_experiment(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<experiment::Model>> {
+    set_archived(state, teacher, id, false).await
+}
+
+async fn get_experiment_teachers(
+    State(state): State<AppState>,
+    _user: AuthUser,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<TeacherInfo>>> {
+    let experiment = experiment::Model::find_by_id_with_db(id, &state.db).await?;
+    let teachers = experiment
+        .find_related(teacher::Entity)
+        .all(&state.db)
+        .await?;
+    Ok(Json(teachers.into_iter().map(TeacherInfo::from).collect()))
+}
+
+async fn add_experiment_teacher(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(body): Json<AddOwner>,
+) -> Result<Json<TeacherInfo>> {
+    let experiment = experiment::Model::find_by_id_with_db(id, &state.db).await?;
+    auth.require_for_experiment_with_db(experiment.id, Permission::ManageExperiment, &state.db)
+        .await?;
+    let teacher = teacher::Entity::find_by_id(body.teacher_pid)
+        .one(&state.db)
+        .await?
+        .ok_or(Error::not_found("Teacher"))?;
+    experiment.add_owner_with_db(teacher.id, &state.db).await?;
+    Ok(Json(teacher.into()))
+}
+
+async fn remove_experiment_teacher(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+    Path((id, teacher_pid)): Path<(i64, i64)>,
+) -> Result<()> {
+    let experiment = experiment::Model::find_by_id_with_db(id, &state.db).await?;
+    auth.require_for_experiment_with_db(experiment.id, Permission::ManageExperiment, &state.db)
+        .await?;
+    experiment
+        .remove_owner_with_db(teacher_pid, &state.db)
+        .await
+}
+
+async fn get_experiment_classes(
+    State(state): State<AppState>,
+    _teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<class::Model>>> {
+    let experiment = experiment::Model::find_by_id_with_db(id, &state.db).await?;
+    let classes = experiment
+        .find_related(class::Entity)
+        .all(&state.db)
+        .await?;
+    Ok(Json(classes))
+}
+
+// 布置和撤销会改变整个班级学生的选修, 教师需要在该班级任教, 或拥有管理班级学生的全局权限
+async fn require_class_member<C>(teacher: &teacher::Model, class_pid: i64, db: &C) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let membership = class_teacher_junction::Entity::find_by_id((class_pid, teacher.id))
+        .one(db)
+        .await?;
+    if membership.is_none() {
+        Subject::teacher(teacher).require(Permission::ManageClassStudents)?;
+    }
+    Ok(())
+}
+
+// 布置给班级后, 班级的全部学生自动选修该实验
+async fn assign_experiment_class(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(body): Json<AssignClass>,
+) -> Result<()> {
+    let teacher = teacher
+        .require_for_experiment_with_db(id, Permission::ManageExperiment, &state.db)
+        .await?;
+    require_class_member(&teacher, body.class_pid, &state.db).await?;
+    services::experiment::assign_class_with_db(id, body.class_pid, &state.db).await
+}
+
+async fn unassign_experiment_class(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path((id, class_pid)): Path<(i64, i64)>,
+) -> Result<()> {
+    let teacher = teacher
+        .require_for_experiment_with_db(id, Permission::ManageExperiment, &state.db)
+        .await?;
+    require_class_member(&teacher, class_pid, &state.db).await?;
+    services::experiment::unassign_class_with_db(id, class_pid, &state.db).await
+}
+
+async fn get_experiment_time_ranges(
+    State(state): State<AppState>,
+    _user: AuthUser,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<experiment_time_ranges::Model>>> {
+    let experiment = experiment::Model::find_by_id_with_db(id, &state.db).await?;
+    let time_ranges = experiment
+        .find_related(experiment_time_ranges::Entity)
+        .order_by_asc(experiment_time_ranges::Column::StartTime)
+        .all(&state.db)
+        .await?;
+    Ok(Json(time_ranges))
+}
+
+async fn list_experiment_series(
+    State(state): State<AppState>,
+    _teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<time_range_series::Model>>> {
+    let series = time_range_series::Entity::find()
+        .filter(time_range_series::Column::ExperimentPid.eq(id))
+        .order_by_asc(time_range_series::Column::Id)
+        .all(&state.db)
+        .await?;
+    Ok(Json(series))
+}
+
+// 只展开规则, 不写入数据库
+async fn preview_experiment_series(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(rule): Json<RecurrenceRule>,
+) -> Result<Json<Vec<Occurrence>>> {
+    teacher
+        .require_for_experiment_with_db(id, Permission::ManageExperiment, &state.db)
+        .await?;
+    let blackout =
+        blackout_date::Model::dates_between_with_db(rule.start_date, rule.end_date, &state.db)
+            .await?;
+    Ok(Json(rule.occurrences(
+        state.config.reservation.timezone(),
+        &blackout,
+    )?))
+}
+
+async fn create_experiment_series(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(rule): Json<RecurrenceRule>,
+) -> Result<Json<GeneratedSeries>> {
+    teacher
+        .require_for_experiment_with_db(id, Permission::ManageExperiment, &state.db)
+        .await?;
+    Ok(Json(
+        services::time_range_series::generate_series_with_db(
+            id,
+            rule,
+            &state.config.reservation,
+            &state.db,
+        )
+        .await?,
+    ))
+}
+
+async fn find_series(
+    state: &AppState,
+    teacher: &AuthTeacher,
+    id: i64,
+    series_pid: i64,
+) -> Result<time_range_series::Model> {
+    teacher
+        .require_for_experiment_with_db(id, Permission::ManageExperiment, &state.db)
+        .await?;
+    let series = time_range_series::Model::find_by_id_with_db(series_pid, &state.db).await?;
+    if series.experiment_pid != id {
+        return Err(Error::not_found("Time range series"));
+    }
+    Ok(series)
+}
+
+async fn update_experiment_series(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path((id, series_pid)): Path<(i64, i64)>,
+    Json(body): Json<SeriesUpdate>,
+) -> Result<Json<SeriesChange>> {
+    let series = find_series(&state, &teacher, id, series_pid).await?;
+    Ok(Json(
+        services::time_range_series::update_series_with_db(
+            &series,
+            body,
+            state.config.reservation.timezone(),
+            &state.db,
+        )
+        .await?,
+    ))
+}
+
+async fn delete_experiment_series(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path((id, series_pid)): Path<(i64, i64)>,
+) -> Result<Json<SeriesChange>> {
+    let series = find_series(&state, &teacher, id, series_pid).await?;
+    Ok(Json(
+        services::time_range_series::delete_series_with_db(series, &state.db).await?,
+    ))
+}
@@ -0,0 +1,42 @@
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    routing::post,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{
+        import::{import_roster_with_db, ImportReport, RosterFormat},
+        rbac::Permission,
+    },
+    error::Result,
+    state::AppState,
+};
+
+use super::auth::AuthTeacher;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ImportQuery {
+    // 为空时根据文件内容判断
+    pub format: Option<RosterFormat>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/roster", post(import_roster))
+}
+
+// 请求体为名单文件的原始内容
+async fn import_roster(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<Json<ImportReport>> {
+    teacher
+        .require_with_db(Permission::ImportRoster, &state.db)
+        .await?;
+    let format = query.format.unwrap_or_else(|| RosterFormat::detect(&body));
+    Ok(Json(import_roster_with_db(&body, format, &state.db).await?))
+}
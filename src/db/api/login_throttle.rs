@@ -0,0 +1,49 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get},
+    Json, Router,
+};
+use sea_orm::EntityTrait;
+
+use crate::{
+    db::{models::login_throttle, rbac::Permission},
+    error::{Error, Result},
+    state::AppState,
+};
+
+use super::auth::AuthTeacher;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_blocked))
+        .route("/:id", delete(delete_throttle))
+}
+
+// 当前被限制登录的账号和IP
+async fn list_blocked(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+) -> Result<Json<Vec<login_throttle::Model>>> {
+    auth.require_with_db(Permission::ManageUsers, &state.db)
+        .await?;
+    Ok(Json(
+        login_throttle::Model::find_blocked_with_db(&state.db).await?,
+    ))
+}
+
+// 删除一条失败记录, 用于解除IP限制
+async fn delete_throttle(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    auth.require_with_db(Permission::ManageUsers, &state.db)
+        .await?;
+    let result = login_throttle::Entity::delete_by_id(id)
+        .exec(&state.db)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(Error::not_found("Login throttle"));
+    }
+    Ok(())
+}
@@ -0,0 +1,542 @@
+use axum::{routing::get, Router};
+
+use crate::state::AppState;
+
+pub mod auth;
+pub mod board;
+pub mod calendar;
+pub mod class;
+pub mod experiment;
+pub mod import;
+pub mod login_throttle;
+pub mod student;
+pub mod teacher;
+pub mod time_range;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .nest("/students", student::router())
+        .nest("/teachers", teacher::router())
+        .nest("/classes", class::router())
+        .nest("/experiments", experiment::router())
+        .nest("/boards", board::router())
+        .nest("/calendar", calendar::router())
+        .nest("/import", import::router())
+        .nest("/login-throttles", login_throttle::router())
+        .nest("/time-ranges", time_range::router())
+        .nest("/reservations", time_range::reservations_router())
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{
+        body::{to_bytes, Body},
+        http::{
+            header::{AUTHORIZATION, CONTENT_TYPE},
+            Method, Request, StatusCode,
+        },
+    };
+    use sea_orm::{DatabaseConnection, EntityTrait, ModelTrait};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{
+        config::Config,
+        db::{
+            models::{class, student, student_refresh_token, teacher, teacher_refresh_token},
+            testing::{
+                test_db, ClassBuilder, ExperimentBuilder, StudentBuilder, TeacherBuilder,
+                TimeRangeBuilder,
+            },
+            token::encode_access_token,
+        },
+        keys::{JwtKeys, KeySet},
+    };
+
+    fn test_state(db: DatabaseConnection) -> AppState {
+        let key_set = |secret: &[u8]| {
+            KeySet::from_secrets(vec![("test".to_string(), secret.to_vec())]).unwrap()
+        };
+        let keys = JwtKeys {
+            student: key_set(b"student secret"),
+            teacher: key_set(b"teacher secret"),
+        };
+        AppState::new(db, keys, Config::default())
+    }
+
+    async fn send(
+        state: &AppState,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let body = match body {
+            Some(body) => {
+                request = request.header(CONTENT_TYPE, "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+        let response = router()
+            .with_state(state.clone())
+            .oneshot(request.body(body).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    async fn get_status(state: &AppState, uri: &str, token: Option<&str>) -> StatusCode {
+        send(state, Method::GET, uri, token, None).await.0
+    }
+
+    async fn post_json(state: &AppState, uri: &str, body: Value) -> (StatusCode, Value) {
+        send(state, Method::POST, uri, None, Some(body)).await
+    }
+
+    #[tokio::test]
+    async fn test_roster_requires_teacher() {
+        let db = test_db().await;
+        let state = test_state(db.clone());
+        let teacher = TeacherBuilder::new().insert(&db).await;
+        let student = StudentBuilder::new().insert(&db).await;
+        let class = ClassBuilder::new()
+            .student(&student)
+            .teacher(&teacher, false)
+            .insert(&db)
+            .await;
+        let teacher_token = encode_access_token(teacher.id, &state.keys.teacher, 60).unwrap();
+        // 学生令牌由另一组密钥签发, 不能冒充教师
+        let student_token = encode_access_token(teacher.id, &state.keys.student, 60).unwrap();
+
+        let uris = [
+            "/students".to_string(),
+            format!("/students/{}", student.id),
+            format!("/students/{}/classes", student.id),
+            "/teachers".to_string(),
+            format!("/teachers/{}", teacher.id),
+            format!("/teachers/{}/classes", teacher.id),
+            "/classes".to_string(),
+            format!("/classes/{}", class.id),
+            format!("/classes/{}/teachers", class.id),
+        ];
+        for uri in &uris {
+            assert_eq!(
+                get_status(&state, uri, None).await,
+                StatusCode::UNAUTHORIZED,
+                "{}",
+                uri
+            );
+            assert_eq!(
+                get_status(&state, uri, Some(&student_token)).await,
+                StatusCode::UNAUTHORIZED,
+                "{}",
+                uri
+            );
+            assert_eq!(
+                get_status(&state, uri, Some(&teacher_token)).await,
+                StatusCode::OK,
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_reports_must_change_password() {
+        let db = test_db().await;
+        let state = test_state(db.clone());
+        let student = StudentBuilder::new()
+            .insert(&db)
+            .await
+            .reset_password_with_db("reset-password".to_string(), &db)
+            .await
+            .unwrap();
+        let teacher = TeacherBuilder::new()
+            .insert(&db)
+            .await
+            .reset_password_with_db("reset-password".to_string(), &db)
+            .await
+            .unwrap();
+        // 重置密码会吊销已有的刷新令牌, 因此在重置之后签发
+        let (student_token, _) = student_refresh_token::Model::issue_with_db(student.id, 3600, &db)
+            .await
+            .unwrap();
+        let (teacher_token, _) = teacher_refresh_token::Model::issue_with_db(teacher.id, 3600, &db)
+            .await
+            .unwrap();
+
+        let (status, body) = post_json(
+            &state,
+            "/students/refresh",
+            json!({ "refresh_token": student_token }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["must_change_password"], json!(true));
+
+        let (status, body) = post_json(
+            &state,
+            "/teachers/refresh",
+            json!({ "refresh_token": teacher_token }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["must_change_password"], json!(true));
+    }
+
+    #[tokio::test]
+    async fn test_reads_require_login() {
+        let db = test_db().await;
+        let state = test_state(db.clone());
+        let teacher = TeacherBuilder::new().insert(&db).await;
+        let student = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new()
+            .teacher(&teacher)
+            .student(&student)
+            .insert(&db)
+            .await;
+        let time_range = TimeRangeBuilder::new(&experiment).insert(&db).await;
+        let teacher_token = encode_access_token(teacher.id, &state.keys.teacher, 60).unwrap();
+        let student_token = encode_access_token(student.id, &state.keys.student, 60).unwrap();
+
+        // 教师和学生都可以查看
+        let shared_uris = [
+            "/experiments".to_string(),
+            format!("/experiments/{}", experiment.id),
+            format!("/experiments/{}/teachers", experiment.id),
+            format!("/experiments/{}/time-ranges", experiment.id),
+            format!("/time-ranges/{}", time_range.id),
+        ];
+        for uri in &shared_uris {
+            assert_eq!(
+                get_status(&state, uri, None).await,
+                StatusCode::UNAUTHORIZED,
+                "{}",
+                uri
+            );
+            for token in [&student_token, &teacher_token] {
+                assert_eq!(
+                    get_status(&state, uri, Some(token)).await,
+                    StatusCode::OK,
+                    "{}",
+                    uri
+                );
+            }
+        }
+
+        // 只有教师可以查看
+        let teacher_uris = [
+            format!("/experiments/{}/classes", experiment.id),
+            format!("/experiments/{}/series", experiment.id),
+            "/boards".to_string(),
+        ];
+        for uri in &teacher_uris {
+            for token in [None, Some(student_token.as_str())] {
+                assert_eq!(
+                    get_status(&state, uri, token).await,
+                    StatusCode::UNAUTHORIZED,
+                    "{}",
+                    uri
+                );
+            }
+            assert_eq!(
+                get_status(&state, uri, Some(&teacher_token)).await,
+                StatusCode::OK,
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_class_admin_boundaries() {
+        let db = test_db().await;
+        let state = test_state(db.clone());
+        let creator = TeacherBuilder::new().insert(&db).await;
+        let colleague = TeacherBuilder::new().insert(&db).await;
+        let outsider = TeacherBuilder::new().insert(&db).await;
+        let admin = teacher::Model::ensure_system_admin_with_db(
+            "root".to_string(),
+            "password".to_string(),
+            &db,
+        )
+        .await
+        .unwrap();
+        let student = StudentBuilder::new().insert(&db).await;
+        let token = |teacher: &teacher::Model| {
+            encode_access_token(teacher.id, &state.keys.teacher, 60).unwrap()
+        };
+        let (creator_token, colleague_token, outsider_token, admin_token) = (
+            token(&creator),
+            token(&colleague),
+            token(&outsider),
+            token(&admin),
+        );
+
+        // 普通教师可以创建空班级, 但不能在创建时拉入已有学生
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            "/classes",
+            Some(&creator_token),
+            Some(json!({ "students": [student.id] })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, class) = send(
+            &state,
+            Method::POST,
+            "/classes",
+            Some(&creator_token),
+            Some(json!({})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let students_uri = format!("/classes/{}/students", class["id"]);
+        let student_uri = format!("{}/{}", students_uri, student.id);
+
+        // 班级管理员不能按主键拉入任意学生, 系统管理员可以
+        let add_student = json!({ "student_pid": student.id });
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &students_uri,
+            Some(&creator_token),
+            Some(add_student.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &students_uri,
+            Some(&admin_token),
+            Some(add_student),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // 班级管理员可以添加教师, 普通成员不能移除教师
+        let teachers_uri = format!("/classes/{}/teachers", class["id"]);
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &teachers_uri,
+            Some(&creator_token),
+            Some(json!({ "teacher_pid": colleague.id })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let creator_uri = format!("{}/{}", teachers_uri, creator.id);
+        let (status, _) = send(
+            &state,
+            Method::DELETE,
+            &creator_uri,
+            Some(&colleague_token),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // 只有班级管理员可以移除已在班级中的学生
+        for token in [&outsider_token, &colleague_token] {
+            let (status, _) = send(&state, Method::DELETE, &student_uri, Some(token), None).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+        }
+        let (status, _) = send(
+            &state,
+            Method::DELETE,
+            &student_uri,
+            Some(&creator_token),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let colleague_uri = format!("{}/{}", teachers_uri, colleague.id);
+        let (status, _) = send(
+            &state,
+            Method::DELETE,
+            &colleague_uri,
+            Some(&creator_token),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let teachers = class::Entity::find_by_id(class["id"].as_i64().unwrap())
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap()
+            .find_related(teacher::Entity)
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(teachers, vec![creator]);
+    }
+
+    #[tokio::test]
+    async fn test_class_admin_cannot_take_over_students() {
+        let db = test_db().await;
+        let state = test_state(db.clone());
+        let teacher = TeacherBuilder::new().insert(&db).await;
+        let admin = teacher::Model::ensure_system_admin_with_db(
+            "root".to_string(),
+            "password".to_string(),
+            &db,
+        )
+        .await
+        .unwrap();
+        let student = StudentBuilder::new().insert(&db).await;
+        let teacher_token = encode_access_token(teacher.id, &state.keys.teacher, 60).unwrap();
+        let admin_token = encode_access_token(admin.id, &state.keys.teacher, 60).unwrap();
+        let reset_uri = format!("/students/{}/password/reset", student.id);
+        let unlock_uri = format!("/students/{}/unlock", student.id);
+        let reset = json!({ "new_password": "taken-over" });
+
+        // 教师创建班级成为班级管理员, 但不能拉入学生再重置其密码
+        let (status, class) = send(
+            &state,
+            Method::POST,
+            "/classes",
+            Some(&teacher_token),
+            Some(json!({})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let students_uri = format!("/classes/{}/students", class["id"]);
+        let add_student = json!({ "student_pid": student.id });
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &students_uri,
+            Some(&teacher_token),
+            Some(add_student.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &reset_uri,
+            Some(&teacher_token),
+            Some(reset.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // 学生由系统管理员加入班级后, 班级管理员仍然不能重置密码或解除登录限制
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &students_uri,
+            Some(&admin_token),
+            Some(add_student),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &reset_uri,
+            Some(&teacher_token),
+            Some(reset.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &unlock_uri,
+            Some(&teacher_token),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(student::Entity::find_by_id(student.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap()
+            .verify_password("password".to_string())
+            .is_ok());
+
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &reset_uri,
+            Some(&admin_token),
+            Some(reset),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_assign_experiment_requires_class_membership() {
+        let db = test_db().await;
+        let state = test_state(db.clone());
+        let owner = TeacherBuilder::new().insert(&db).await;
+        let admin = teacher::Model::ensure_system_admin_with_db(
+            "root".to_string(),
+            "password".to_string(),
+            &db,
+        )
+        .await
+        .unwrap();
+        let experiment = ExperimentBuilder::new().teacher(&owner).insert(&db).await;
+        let student = StudentBuilder::new().insert(&db).await;
+        let own_class = ClassBuilder::new()
+            .student(&student)
+            .teacher(&owner, false)
+            .insert(&db)
+            .await;
+        let other_class = ClassBuilder::new().student(&student).insert(&db).await;
+        let owner_token = encode_access_token(owner.id, &state.keys.teacher, 60).unwrap();
+        let admin_token = encode_access_token(admin.id, &state.keys.teacher, 60).unwrap();
+        let classes_uri = format!("/experiments/{}/classes", experiment.id);
+
+        // 实验负责人只能把实验布置给自己任教的班级
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &classes_uri,
+            Some(&owner_token),
+            Some(json!({ "class_pid": other_class.id })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &classes_uri,
+            Some(&owner_token),
+            Some(json!({ "class_pid": own_class.id })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // 系统管理员不受班级成员身份限制
+        let (status, _) = send(
+            &state,
+            Method::POST,
+            &classes_uri,
+            Some(&admin_token),
+            Some(json!({ "class_pid": other_class.id })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let other_uri = format!("{}/{}", classes_uri, other_class.id);
+        let (status, _) = send(&state, Method::DELETE, &other_uri, Some(&owner_token), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,265 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use jsonwebtoken::jwk::JwkSet;
+use sea_orm::{ConnectionTrait, EntityTrait, ModelTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{
+        models::{
+            class,
+            login_throttle::{self, ThrottleScope},
+            student, student_refresh_token,
+            student_strike::{self, Standing},
+            student_suspension,
+        },
+        rbac::Permission,
+        reject_unknown_account,
+        token::{encode_access_token, TokenPair},
+    },
+    error::{Error, Result},
+    state::AppState,
+};
+
+use super::auth::{
+    check_login_throttle_with_db, record_login_result_with_db, AuthTeacher, ChangePasswordRequest,
+    ClientIp, LoginRequest, RefreshRequest, ResetPasswordRequest, StudentSession,
+};
+
+// 对外返回的学生信息, 不包含密码哈希
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StudentInfo {
+    pub id: i64,
+    pub student_id: Option<String>,
+    pub account: Option<String>,
+    pub name: Option<String>,
+}
+
+impl From<student::Model> for StudentInfo {
+    fn from(model: student::Model) -> Self {
+        Self {
+            id: model.id,
+            student_id: model.student_id,
+            account: model.account,
+            name: model.name,
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/login", post(login))
+        .route("/refresh", post(refresh))
+        .route("/jwks.json", get(jwks))
+        .route("/", get(list_students))
+        .route("/:id", get(get_student))
+        .route("/:id/classes", get(get_student_classes))
+        .route("/password", post(change_password))
+        .route("/:id/password/reset", post(reset_password))
+        .route("/:id/unlock", post(unlock_student))
+        .route("/:id/standing", get(get_student_standing))
+        .route("/:id/suspension", delete(lift_student_suspension))
+}
+
+async fn list_students(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+) -> Result<Json<Vec<StudentInfo>>> {
+    teacher
+        .require_with_db(Permission::ViewClassRoster, &state.db)
+        .await?;
+    let students = student::Entity::find().all(&state.db).await?;
+    Ok(Json(students.into_iter().map(StudentInfo::from).collect()))
+}
+
+async fn find_student<C>(id: i64, db: &C) -> Result<student::Model>
+where
+    C: ConnectionTrait,
+{
+    student::Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or(Error::not_found("Student"))
+}
+
+async fn get_student(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<StudentInfo>> {
+    teacher
+        .require_for_student_with_db(id, Permission::ViewClassRoster, &state.db)
+        .await?;
+    Ok(Json(find_student(id, &state.db).await?.into()))
+}
+
+async fn get_student_classes(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<class::Model>>> {
+    teacher
+        .require_for_student_with_db(id, Permission::ViewClassRoster, &state.db)
+        .await?;
+    let student = find_student(id, &state.db).await?;
+    let classes = student.find_related(class::Entity).all(&state.db).await?;
+    Ok(Json(classes))
+}
+
+async fn login(
+    State(state): State<AppState>,
+    ip: ClientIp,
+    Json(body): Json<LoginRequest>,
+) -> Result<Json<TokenPair>> {
+    let scope = ThrottleScope::Student;
+    let LoginRequest { account, password } = body;
+    check_login_throttle_with_db(scope, &account, &ip, &state.db).await?;
+    let result = async {
+        // 不区分账号不存在和密码错误, 避免泄露账号是否存在
+        let student =
+            match student::Model::find_by_student_id_or_account_with_db(account.clone(), &state.db)
+                .await
+            {
+                Ok(student) => Some(student),
+                Err(Error::NotFound(_)) => None,
+                Err(e) => return Err(e),
+            };
+        tokio::task::spawn_blocking(move || match student {
+            Some(student) => student
+                .verify_password(password.clone())
+                .map(|_| (student, password)),
+            None => Err(reject_unknown_account(&password)),
+        })
+        .await
+        .map_err(Error::internal)?
+    }
+    .await;
+    let (mut student, password) =
+        record_login_result_with_db(result, scope, &account, &ip, &state.config.login, &state.db)
+            .await?;
+    // 哈希参数已过时时, 用本次登录的明文密码按当前参数重新哈希
+    if student.needs_rehash() {
+        student = student.set_password_with_db(password, &state.db).await?;
+    }
+
+    let jwt = &state.config.jwt;
+    let keys = &state.keys;
+    let access_token =
+        encode_access_token(student.id, &keys.student, jwt.access_token_lifetime_secs)?;
+    let (refresh_token, _) = student_refresh_token::Model::issue_with_db(
+        student.id,
+        jwt.refresh_token_lifetime_secs,
+        &state.db,
+    )
+    .await?;
+    Ok(Json(TokenPair {
+        must_change_password: student.must_change_password,
+        ..TokenPair::new(access_token, refresh_token, jwt.access_token_lifetime_secs)
+    }))
+}
+
+async fn refresh(
+    State(state): State<AppState>,
+    Json(body): Json<RefreshRequest>,
+) -> Result<Json<TokenPair>> {
+    let jwt = &state.config.jwt;
+    let (refresh_token, token) = student_refresh_token::Model::rotate_with_db(
+        &body.refresh_token,
+        jwt.refresh_token_lifetime_secs,
+        &state.db,
+    )
+    .await?;
+    // 与登录一致, 需要修改密码时提示客户端
+    let student = student::Entity::find_by_id(token.student_pid)
+        .one(&state.db)
+        .await?
+        .ok_or(Error::Unauthorized("Student no longer exists".to_string()))?;
+    let keys = &state.keys;
+    let access_token =
+        encode_access_token(student.id, &keys.student, jwt.access_token_lifetime_secs)?;
+    Ok(Json(TokenPair {
+        must_change_password: student.must_change_password,
+        ..TokenPair::new(access_token, refresh_token, jwt.access_token_lifetime_secs)
+    }))
+}
+
+// 公开学生令牌的校验公钥
+async fn jwks(State(state): State<AppState>) -> Result<Json<JwkSet>> {
+    let keys = &state.keys;
+    Ok(Json(keys.student.jwks()))
+}
+
+// 修改本人密码, 强制改密期间也可以调用
+async fn change_password(
+    State(state): State<AppState>,
+    StudentSession(student_pid): StudentSession,
+    Json(body): Json<ChangePasswordRequest>,
+) -> Result<()> {
+    let student = find_student(student_pid, &state.db).await?;
+    student
+        .change_password_with_db(body.old_password, body.new_password, &state.db)
+        .await?;
+    Ok(())
+}
+
+// 只有拥有全局权限的系统管理员可以重置学生密码, 班级管理员不行
+async fn reset_password(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(body): Json<ResetPasswordRequest>,
+) -> Result<()> {
+    teacher
+        .require_with_db(Permission::ResetStudentPassword, &state.db)
+        .await?;
+    let student = find_student(id, &state.db).await?;
+    student
+        .reset_password_with_db(body.new_password, &state.db)
+        .await?;
+    Ok(())
+}
+
+// 解除学生账号的登录限制, 学号和账号都可以用于登录, 需要一起清除
+async fn unlock_student(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    teacher
+        .require_with_db(Permission::ResetStudentPassword, &state.db)
+        .await?;
+    let student = find_student(id, &state.db).await?;
+    for key in [student.student_id, student.account].into_iter().flatten() {
+        login_throttle::Model::clear_with_db(ThrottleScope::Student, &key, &state.db).await?;
+    }
+    Ok(())
+}
+
+async fn get_student_standing(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<Standing>> {
+    teacher
+        .require_for_student_with_db(id, Permission::ViewClassRoster, &state.db)
+        .await?;
+    find_student(id, &state.db).await?;
+    Ok(Json(
+        student_strike::Model::standing_with_db(id, &state.config.reservation, &state.db).await?,
+    ))
+}
+
+// 提前解除学生的预约暂停
+async fn lift_student_suspension(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    teacher
+        .require_with_db(Permission::LiftSuspension, &state.db)
+        .await?;
+    student_suspension::Model::lift_with_db(id, &state.db).await
+}
@@ -0,0 +1,254 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post, put},
+    Json, Router,
+};
+use jsonwebtoken::jwk::JwkSet;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait, ModelTrait, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{
+        models::{
+            class,
+            login_throttle::{self, ThrottleScope},
+            teacher::{self, TeacherRole},
+            teacher_refresh_token,
+        },
+        rbac::Permission,
+        reject_unknown_account,
+        token::{encode_access_token, TokenPair},
+    },
+    error::{Error, Result},
+    state::AppState,
+};
+
+use super::auth::{
+    check_login_throttle_with_db, record_login_result_with_db, AuthTeacher, ChangePasswordRequest,
+    ClientIp, LoginRequest, RefreshRequest, ResetPasswordRequest, TeacherSession,
+};
+
+// 对外返回的教师信息, 不包含密码哈希
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TeacherInfo {
+    pub id: i64,
+    pub teacher_id: Option<String>,
+    pub account: Option<String>,
+    pub name: Option<String>,
+    pub role: TeacherRole,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdateRole {
+    pub role: TeacherRole,
+}
+
+impl From<teacher::Model> for TeacherInfo {
+    fn from(model: teacher::Model) -> Self {
+        Self {
+            id: model.id,
+            teacher_id: model.teacher_id,
+            account: model.account,
+            name: model.name,
+            role: model.role,
+        }
+    }
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/login", post(login))
+        .route("/refresh", post(refresh))
+        .route("/jwks.json", get(jwks))
+        .route("/", get(list_teachers))
+        .route("/:id", get(get_teacher))
+        .route("/:id/classes", get(get_teacher_classes))
+        .route("/password", post(change_password))
+        .route("/:id/password/reset", post(reset_password))
+        .route("/:id/unlock", post(unlock_teacher))
+        .route("/:id/role", put(update_teacher_role))
+}
+
+async fn list_teachers(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+) -> Result<Json<Vec<TeacherInfo>>> {
+    auth.require_with_db(Permission::ViewClassRoster, &state.db)
+        .await?;
+    let teachers = teacher::Entity::find().all(&state.db).await?;
+    Ok(Json(teachers.into_iter().map(TeacherInfo::from).collect()))
+}
+
+async fn find_teacher<C>(id: i64, db: &C) -> Result<teacher::Model>
+where
+    C: ConnectionTrait,
+{
+    teacher::Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or(Error::not_found("Teacher"))
+}
+
+async fn get_teacher(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<TeacherInfo>> {
+    auth.require_with_db(Permission::ViewClassRoster, &state.db)
+        .await?;
+    Ok(Json(find_teacher(id, &state.db).await?.into()))
+}
+
+async fn get_teacher_classes(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<class::Model>>> {
+    auth.require_with_db(Permission::ViewClassRoster, &state.db)
+        .await?;
+    let teacher = find_teacher(id, &state.db).await?;
+    let classes = teacher.find_related(class::Entity).all(&state.db).await?;
+    Ok(Json(classes))
+}
+
+// 只有系统管理员可以修改教师的全局角色
+async fn update_teacher_role(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(body): Json<UpdateRole>,
+) -> Result<Json<TeacherInfo>> {
+    auth.require_with_db(Permission::ManageUsers, &state.db)
+        .await?;
+    let teacher = find_teacher(id, &state.db).await?;
+    let mut active: teacher::ActiveModel = teacher.into();
+    active.role = Set(body.role);
+    Ok(Json(active.update(&state.db).await?.into()))
+}
+
+async fn login(
+    State(state): State<AppState>,
+    ip: ClientIp,
+    Json(body): Json<LoginRequest>,
+) -> Result<Json<TokenPair>> {
+    let scope = ThrottleScope::Teacher;
+    let LoginRequest { account, password } = body;
+    check_login_throttle_with_db(scope, &account, &ip, &state.db).await?;
+    let result = async {
+        // 不区分账号不存在和密码错误, 避免泄露账号是否存在
+        let teacher =
+            match teacher::Model::find_by_teacher_id_or_account_with_db(account.clone(), &state.db)
+                .await
+            {
+                Ok(teacher) => Some(teacher),
+                Err(Error::NotFound(_)) => None,
+                Err(e) => return Err(e),
+            };
+        tokio::task::spawn_blocking(move || match teacher {
+            Some(teacher) => teacher
+                .verify_password(password.clone())
+                .map(|_| (teacher, password)),
+            None => Err(reject_unknown_account(&password)),
+        })
+        .await
+        .map_err(Error::internal)?
+    }
+    .await;
+    let (mut teacher, password) =
+        record_login_result_with_db(result, scope, &account, &ip, &state.config.login, &state.db)
+            .await?;
+    // 哈希参数已过时时, 用本次登录的明文密码按当前参数重新哈希
+    if teacher.needs_rehash() {
+        teacher = teacher.set_password_with_db(password, &state.db).await?;
+    }
+
+    let jwt = &state.config.jwt;
+    let keys = &state.keys;
+    let access_token =
+        encode_access_token(teacher.id, &keys.teacher, jwt.access_token_lifetime_secs)?;
+    let (refresh_token, _) = teacher_refresh_token::Model::issue_with_db(
+        teacher.id,
+        jwt.refresh_token_lifetime_secs,
+        &state.db,
+    )
+    .await?;
+    Ok(Json(TokenPair {
+        must_change_password: teacher.must_change_password,
+        ..TokenPair::new(access_token, refresh_token, jwt.access_token_lifetime_secs)
+    }))
+}
+
+async fn refresh(
+    State(state): State<AppState>,
+    Json(body): Json<RefreshRequest>,
+) -> Result<Json<TokenPair>> {
+    let jwt = &state.config.jwt;
+    let (refresh_token, token) = teacher_refresh_token::Model::rotate_with_db(
+        &body.refresh_token,
+        jwt.refresh_token_lifetime_secs,
+        &state.db,
+    )
+    .await?;
+    // 与登录一致, 需要修改密码时提示客户端
+    let teacher = teacher::Entity::find_by_id(token.teacher_pid)
+        .one(&state.db)
+        .await?
+        .ok_or(Error::Unauthorized("Teacher no longer exists".to_string()))?;
+    let keys = &state.keys;
+    let access_token =
+        encode_access_token(teacher.id, &keys.teacher, jwt.access_token_lifetime_secs)?;
+    Ok(Json(TokenPair {
+        must_change_password: teacher.must_change_password,
+        ..TokenPair::new(access_token, refresh_token, jwt.access_token_lifetime_secs)
+    }))
+}
+
+// 公开教师令牌的校验公钥
+async fn jwks(State(state): State<AppState>) -> Result<Json<JwkSet>> {
+    let keys = &state.keys;
+    Ok(Json(keys.teacher.jwks()))
+}
+
+// 修改本人密码, 强制改密期间也可以调用
+async fn change_password(
+    State(state): State<AppState>,
+    TeacherSession(teacher_pid): TeacherSession,
+    Json(body): Json<ChangePasswordRequest>,
+) -> Result<()> {
+    let teacher = find_teacher(teacher_pid, &state.db).await?;
+    teacher
+        .change_password_with_db(body.old_password, body.new_password, &state.db)
+        .await?;
+    Ok(())
+}
+
+// 只有系统管理员可以重置教师密码
+async fn reset_password(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+    Path(id): Path<i64>,
+    Json(body): Json<ResetPasswordRequest>,
+) -> Result<()> {
+    auth.require_with_db(Permission::ManageUsers, &state.db)
+        .await?;
+    let teacher = find_teacher(id, &state.db).await?;
+    teacher
+        .reset_password_with_db(body.new_password, &state.db)
+        .await?;
+    Ok(())
+}
+
+// 解除教师账号的登录限制
+async fn unlock_teacher(
+    State(state): State<AppState>,
+    auth: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    auth.require_with_db(Permission::ManageUsers, &state.db)
+        .await?;
+    let teacher = find_teacher(id, &state.db).await?;
+    for key in [teacher.teacher_id, teacher.account].into_iter().flatten() {
+        login_throttle::Model::clear_with_db(ThrottleScope::Teacher, &key, &state.db).await?;
+    }
+    Ok(())
+}
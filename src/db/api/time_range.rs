@@ -0,0 +1,329 @@
+use axum::{
+    extract::{Path, State},
+    response::Response,
+    routing::{get, post, put},
+    Json, Router,
+};
+use std::collections::HashMap;
+
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{
+        models::{
+            board, experiment_time_ranges, experiment_time_ranges_student_junction,
+            experiment_time_ranges_waitlist,
+            student_strike::{self, Standing, StrikeOutcome},
+        },
+        rbac::Permission,
+        services::{
+            calendar::reservation_event_with_db,
+            cancellation::{cancel_own_reservation_with_db, mark_no_show_with_db, CancelOutcome},
+        },
+    },
+    error::{Error, Result},
+    state::AppState,
+};
+
+use super::{
+    auth::{AuthStudent, AuthTeacher, AuthUser},
+    calendar::ics_response,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReservationInfo {
+    pub time_range: experiment_time_ranges::Model,
+    pub reserved_at: chrono::DateTime<chrono::Utc>,
+    pub board: Option<board::Model>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WaitlistInfo {
+    pub entry: experiment_time_ranges_waitlist::Model,
+    // 排队位置, 从1开始
+    pub position: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AssignBoard {
+    // 为空表示取消分配
+    pub board_pid: Option<i64>,
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/:id", get(get_time_range))
+        .route("/:id/reservation", post(reserve).delete(cancel_reservation))
+        .route(
+            "/:id/waitlist",
+            get(get_waitlist_position)
+                .post(join_waitlist)
+                .delete(leave_waitlist),
+        )
+        .route(
+            "/:id/reservations/:student_pid",
+            axum::routing::delete(cancel_student_reservation),
+        )
+        .route("/:id/reservations/:student_pid/board", put(assign_board))
+        .route("/:id/reservations/:student_pid/no-show", post(mark_no_show))
+        .route("/:id/boards/assign", post(assign_free_boards))
+}
+
+// 当前学生的所有预约
+pub fn reservations_router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_reservations))
+        .route("/:id/ics", get(download_reservation))
+        .route("/standing", get(get_standing))
+        .route("/promotions", get(list_promotions))
+        .route("/promotions/:id/ack", post(acknowledge_promotion))
+}
+
+async fn get_time_range(
+    State(state): State<AppState>,
+    _user: AuthUser,
+    Path(id): Path<i64>,
+) -> Result<Json<experiment_time_ranges::Model>> {
+    Ok(Json(
+        experiment_time_ranges::Model::find_by_id_with_db(id, &state.db).await?,
+    ))
+}
+
+async fn reserve(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+    Path(id): Path<i64>,
+) -> Result<Json<experiment_time_ranges_student_junction::Model>> {
+    let time_range = experiment_time_ranges::Model::find_by_id_with_db(id, &state.db).await?;
+    Ok(Json(
+        time_range
+            .reserve_with_db(student_pid, &state.config.reservation, &state.db)
+            .await?,
+    ))
+}
+
+async fn get_waitlist_position(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+    Path(id): Path<i64>,
+) -> Result<Json<WaitlistInfo>> {
+    let entry =
+        experiment_time_ranges_waitlist::Model::find_waiting_with_db(id, student_pid, &state.db)
+            .await?
+            .ok_or(Error::not_found("Waitlist entry"))?;
+    let position = entry.position_with_db(&state.db).await?;
+    Ok(Json(WaitlistInfo { entry, position }))
+}
+
+async fn join_waitlist(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+    Path(id): Path<i64>,
+) -> Result<Json<WaitlistInfo>> {
+    let time_range = experiment_time_ranges::Model::find_by_id_with_db(id, &state.db).await?;
+    let entry =
+        experiment_time_ranges_waitlist::Model::join_with_db(&time_range, student_pid, &state.db)
+            .await?;
+    let position = entry.position_with_db(&state.db).await?;
+    Ok(Json(WaitlistInfo { entry, position }))
+}
+
+async fn leave_waitlist(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+    Path(id): Path<i64>,
+) -> Result<()> {
+    experiment_time_ranges_waitlist::Model::leave_with_db(id, student_pid, &state.db).await
+}
+
+// 尚未确认的递补通知
+async fn list_promotions(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+) -> Result<Json<Vec<experiment_time_ranges_waitlist::Model>>> {
+    Ok(Json(
+        experiment_time_ranges_waitlist::Model::find_unacknowledged_with_db(student_pid, &state.db)
+            .await?,
+    ))
+}
+
+async fn acknowledge_promotion(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+    Path(id): Path<i64>,
+) -> Result<Json<experiment_time_ranges_waitlist::Model>> {
+    let entry =
+        experiment_time_ranges_waitlist::Model::find_own_with_db(id, student_pid, &state.db)
+            .await?;
+    Ok(Json(entry.acknowledge_with_db(&state.db).await?))
+}
+
+// 学生取消自己的预约, 按实验的取消规则判断是否记录违约
+async fn cancel_reservation(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+    Path(id): Path<i64>,
+) -> Result<Json<CancelOutcome>> {
+    let time_range = experiment_time_ranges::Model::find_by_id_with_db(id, &state.db).await?;
+    Ok(Json(
+        cancel_own_reservation_with_db(
+            &time_range,
+            student_pid,
+            &state.config.reservation,
+            &state.db,
+        )
+        .await?,
+    ))
+}
+
+// 由实验负责人或实验室人员登记缺席
+async fn mark_no_show(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path((id, student_pid)): Path<(i64, i64)>,
+) -> Result<Json<StrikeOutcome>> {
+    let time_range = experiment_time_ranges::Model::find_by_id_with_db(id, &state.db).await?;
+    teacher
+        .require_for_experiment_with_db(
+            time_range.experiment_pid,
+            Permission::MarkNoShow,
+            &state.db,
+        )
+        .await?;
+    Ok(Json(
+        mark_no_show_with_db(
+            &time_range,
+            student_pid,
+            &state.config.reservation,
+            &state.db,
+        )
+        .await?,
+    ))
+}
+
+// 当前学生的违约次数和预约暂停情况
+async fn get_standing(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+) -> Result<Json<Standing>> {
+    Ok(Json(
+        student_strike::Model::standing_with_db(student_pid, &state.config.reservation, &state.db)
+            .await?,
+    ))
+}
+
+// 由实验室人员或管理员取消任意学生的预约
+async fn cancel_student_reservation(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path((id, student_pid)): Path<(i64, i64)>,
+) -> Result<Json<Option<experiment_time_ranges_waitlist::Model>>> {
+    teacher
+        .require_with_db(Permission::CancelAnyReservation, &state.db)
+        .await?;
+    let time_range = experiment_time_ranges::Model::find_by_id_with_db(id, &state.db).await?;
+    Ok(Json(
+        time_range
+            .cancel_reservation_with_db(student_pid, &state.db)
+            .await?,
+    ))
+}
+
+async fn list_reservations(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+) -> Result<Json<Vec<ReservationInfo>>> {
+    let reservations = experiment_time_ranges_student_junction::Entity::find()
+        .filter(experiment_time_ranges_student_junction::Column::StudentPid.eq(student_pid))
+        .find_also_related(experiment_time_ranges::Entity)
+        .order_by_asc(experiment_time_ranges::Column::StartTime)
+        .all(&state.db)
+        .await?;
+    let boards: HashMap<i64, board::Model> = board::Entity::find()
+        .filter(
+            board::Column::Id.is_in(
+                reservations
+                    .iter()
+                    .filter_map(|(reservation, _)| reservation.board_pid),
+            ),
+        )
+        .all(&state.db)
+        .await?
+        .into_iter()
+        .map(|board| (board.id, board))
+        .collect();
+    Ok(Json(
+        reservations
+            .into_iter()
+            .filter_map(|(reservation, time_range)| {
+                time_range.map(|time_range| ReservationInfo {
+                    time_range,
+                    reserved_at: reservation.created_at,
+                    board: reservation
+                        .board_pid
+                        .and_then(|board_pid| boards.get(&board_pid).cloned()),
+                })
+            })
+            .collect(),
+    ))
+}
+
+// 下载单个预约的.ics文件, id为时间段主键
+async fn download_reservation(
+    State(state): State<AppState>,
+    AuthStudent(student_pid): AuthStudent,
+    Path(id): Path<i64>,
+) -> Result<Response> {
+    let event = reservation_event_with_db(id, student_pid, &state.db).await?;
+    let filename = format!("reservation-{}.ics", id);
+    Ok(ics_response(
+        &event.summary,
+        std::slice::from_ref(&event),
+        Some(&filename),
+    ))
+}
+
+// 由实验室人员为某个预约指定开发板
+async fn assign_board(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path((id, student_pid)): Path<(i64, i64)>,
+    Json(body): Json<AssignBoard>,
+) -> Result<Json<experiment_time_ranges_student_junction::Model>> {
+    teacher
+        .require_with_db(Permission::AssignBoards, &state.db)
+        .await?;
+    let reservation =
+        experiment_time_ranges_student_junction::Entity::find_by_id((id, student_pid))
+            .one(&state.db)
+            .await?
+            .ok_or(Error::not_found("Reservation"))?;
+    let reservation = match body.board_pid {
+        Some(board_pid) => {
+            reservation
+                .assign_board_with_db(board_pid, &state.db)
+                .await?
+        }
+        None => reservation.unassign_board_with_db(&state.db).await?,
+    };
+    Ok(Json(reservation))
+}
+
+async fn assign_free_boards(
+    State(state): State<AppState>,
+    teacher: AuthTeacher,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<experiment_time_ranges_student_junction::Model>>> {
+    teacher
+        .require_with_db(Permission::AssignBoards, &state.db)
+        .await?;
+    let time_range = experiment_time_ranges::Model::find_by_id_with_db(id, &state.db).await?;
+    Ok(Json(
+        experiment_time_ranges_student_junction::Model::assign_free_boards_with_db(
+            &time_range,
+            &state.db,
+        )
+        .await?,
+    ))
+}
@@ -0,0 +1,28 @@
+// 数据库连接, 连接由调用方持有并显式传递
+
+use anyhow::Result;
+use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use sea_orm_migration::MigratorTrait;
+
+use super::migrations::Migrator;
+use crate::config::DatabaseConfig;
+
+pub async fn connect(config: &DatabaseConfig) -> Result<DatabaseConnection> {
+    let mut options = ConnectOptions::new(config.url.clone());
+    options
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections);
+    Ok(Database::connect(options).await?)
+}
+
+// 执行所有未应用的迁移
+pub async fn init_db(db: &DatabaseConnection) -> Result<()> {
+    Migrator::up(db, None).await?;
+    Ok(())
+}
+
+// 删除所有表并重新执行迁移
+pub async fn reinit_db(db: &DatabaseConnection) -> Result<()> {
+    Migrator::fresh(db).await?;
+    Ok(())
+}
@@ -0,0 +1,133 @@
+// 生成iCalendar(RFC 5545)格式的日历, 用于导出预约和订阅日历
+
+use chrono::{DateTime, Utc};
+
+const PRODID: &str = "-//fpga_reserve//Reservations//ZH";
+// 每行最多75个字节, 超出部分折行
+const MAX_LINE_OCTETS: usize = 75;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CalendarEvent {
+    // 全局唯一, 同一事件在多次导出中保持不变, 日历客户端据此更新而不是重复添加
+    pub uid: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    // 最后修改时间
+    pub stamp: DateTime<Utc>,
+}
+
+fn format_time(time: &DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// 转义文本中的特殊字符
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// 按字节数折行, 不拆分多字节字符, 续行以空格开头
+fn push_line(output: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if octets + len > MAX_LINE_OCTETS {
+            output.push_str("\r\n ");
+            // 续行开头的空格也计入长度
+            octets = 1;
+        }
+        output.push(c);
+        octets += len;
+    }
+    output.push_str("\r\n");
+}
+
+pub fn render_calendar(name: &str, events: &[CalendarEvent]) -> String {
+    let mut output = String::new();
+    push_line(&mut output, "BEGIN:VCALENDAR");
+    push_line(&mut output, "VERSION:2.0");
+    push_line(&mut output, &format!("PRODID:{}", PRODID));
+    push_line(&mut output, "CALSCALE:GREGORIAN");
+    push_line(&mut output, "METHOD:PUBLISH");
+    push_line(&mut output, &format!("X-WR-CALNAME:{}", escape_text(name)));
+    for event in events {
+        push_line(&mut output, "BEGIN:VEVENT");
+        push_line(&mut output, &format!("UID:{}", event.uid));
+        push_line(
+            &mut output,
+            &format!("DTSTAMP:{}", format_time(&event.stamp)),
+        );
+        push_line(
+            &mut output,
+            &format!("DTSTART:{}", format_time(&event.start)),
+        );
+        push_line(&mut output, &format!("DTEND:{}", format_time(&event.end)));
+        push_line(
+            &mut output,
+            &format!("SUMMARY:{}", escape_text(&event.summary)),
+        );
+        if let Some(description) = &event.description {
+            push_line(
+                &mut output,
+                &format!("DESCRIPTION:{}", escape_text(description)),
+            );
+        }
+        if let Some(location) = &event.location {
+            push_line(&mut output, &format!("LOCATION:{}", escape_text(location)));
+        }
+        push_line(&mut output, "END:VEVENT");
+    }
+    push_line(&mut output, "END:VCALENDAR");
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_calendar() {
+        let start = "2024-09-05T06:00:00Z".parse().unwrap();
+        let event = CalendarEvent {
+            uid: "reservation-1-2@fpga_reserve".to_string(),
+            start,
+            end: "2024-09-05T08:00:00Z".parse().unwrap(),
+            summary: "数字时钟; 第1次, 实验".to_string(),
+            description: Some("开发板: EGO1\n序列号: 001".to_string()),
+            location: None,
+            stamp: start,
+        };
+        let calendar = render_calendar("我的实验", &[event]);
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert!(calendar.contains("DTSTART:20240905T060000Z\r\n"));
+        assert!(calendar.contains("SUMMARY:数字时钟\\; 第1次\\, 实验\r\n"));
+        assert!(calendar.contains("DESCRIPTION:开发板: EGO1\\n序列号: 001\r\n"));
+        assert!(!calendar.contains("LOCATION"));
+    }
+
+    #[test]
+    fn test_fold_long_lines() {
+        let mut output = String::new();
+        let line = format!("SUMMARY:{}", "实".repeat(40));
+        push_line(&mut output, &line);
+        for physical in output.trim_end_matches("\r\n").split("\r\n") {
+            assert!(physical.len() <= MAX_LINE_OCTETS);
+        }
+        // 去掉折行后还原为原始内容
+        assert_eq!(output.replace("\r\n ", ""), format!("{}\r\n", line));
+    }
+}
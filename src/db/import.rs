@@ -0,0 +1,441 @@
+// 从教务处提供的CSV/XLSX名单批量导入学生和班级
+//
+// 名单第一行为表头, 支持以下列(中英文均可):
+// - student_id / 学号 (必填)
+// - class_id / 班级 (必填)
+// - name / 姓名
+// - account / 账号
+// - password / 密码, 为空时使用学号作为初始密码
+//
+// 每一行在独立的事务中导入, 单行失败不影响其他行.
+
+use std::io::Cursor;
+
+use calamine::{Reader, Xlsx};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use super::models::{class, class_student_junction, student};
+use crate::error::{Error, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RosterFormat {
+    Csv,
+    Xlsx,
+}
+
+impl RosterFormat {
+    // xlsx文件是zip压缩包, 以PK开头
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"PK\x03\x04") {
+            RosterFormat::Xlsx
+        } else {
+            RosterFormat::Csv
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterRow {
+    // 在文件中的行号, 表头为第1行
+    pub line: usize,
+    pub student_id: String,
+    pub class_id: String,
+    pub name: Option<String>,
+    pub account: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowStatus {
+    Created,
+    Updated,
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowReport {
+    pub line: usize,
+    pub student_id: Option<String>,
+    pub status: RowStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub created: usize,
+    pub updated: usize,
+    pub failed: usize,
+    pub rows: Vec<RowReport>,
+}
+
+impl ImportReport {
+    fn push(&mut self, report: RowReport) {
+        match report.status {
+            RowStatus::Created => self.created += 1,
+            RowStatus::Updated => self.updated += 1,
+            RowStatus::Failed => self.failed += 1,
+        }
+        self.rows.push(report);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    StudentId,
+    ClassId,
+    Name,
+    Account,
+    Password,
+}
+
+impl Field {
+    fn from_header(header: &str) -> Option<Self> {
+        match header.trim().to_lowercase().as_str() {
+            "student_id" | "学号" => Some(Field::StudentId),
+            "class_id" | "class" | "班级" => Some(Field::ClassId),
+            "name" | "姓名" => Some(Field::Name),
+            "account" | "账号" => Some(Field::Account),
+            "password" | "密码" => Some(Field::Password),
+            _ => None,
+        }
+    }
+}
+
+// 表格中的一行及其在原文件中的行号(从1开始)
+type Line = (usize, Vec<String>);
+
+// 把表格的行转换为名单行, 行内容不合法时返回该行的错误
+fn parse_rows(table: Vec<Line>) -> Result<Vec<Result<RosterRow, RowReport>>> {
+    let mut table = table.into_iter();
+    let headers: Vec<Option<Field>> = table
+        .next()
+        .ok_or(Error::BadRequest("Roster is empty".to_string()))?
+        .1
+        .iter()
+        .map(|header| Field::from_header(header))
+        .collect();
+    for required in [Field::StudentId, Field::ClassId] {
+        if !headers.contains(&Some(required)) {
+            return Err(Error::BadRequest(format!(
+                "Roster is missing required column {:?}",
+                required
+            )));
+        }
+    }
+
+    Ok(table
+        .filter(|(_, cells)| cells.iter().any(|cell| !cell.trim().is_empty()))
+        .map(|(line, cells)| {
+            let mut row = RosterRow {
+                line,
+                ..Default::default()
+            };
+            for (field, cell) in headers.iter().zip(cells) {
+                let cell = cell.trim().to_string();
+                let value = (!cell.is_empty()).then_some(cell);
+                match field {
+                    Some(Field::StudentId) => row.student_id = value.unwrap_or_default(),
+                    Some(Field::ClassId) => row.class_id = value.unwrap_or_default(),
+                    Some(Field::Name) => row.name = value,
+                    Some(Field::Account) => row.account = value,
+                    Some(Field::Password) => row.password = value,
+                    None => {}
+                }
+            }
+            if row.student_id.is_empty() || row.class_id.is_empty() {
+                return Err(RowReport {
+                    line,
+                    student_id: (!row.student_id.is_empty()).then(|| row.student_id.clone()),
+                    status: RowStatus::Failed,
+                    error: Some("student_id and class_id are required".to_string()),
+                });
+            }
+            Ok(row)
+        })
+        .collect())
+}
+
+// csv会跳过空行, 但记录的位置停留在被跳过的空行上, 因此先跳过换行符再按换行数计算行号
+fn read_csv(bytes: &[u8]) -> Result<Vec<Line>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(bytes);
+    reader
+        .records()
+        .map(|record| {
+            record
+                .map(|record| {
+                    let offset = record
+                        .position()
+                        .map_or(0, |position| position.byte() as usize)
+                        .min(bytes.len());
+                    let start = offset
+                        + bytes[offset..]
+                            .iter()
+                            .take_while(|&&byte| byte == b'\r' || byte == b'\n')
+                            .count();
+                    let line = bytes[..start].iter().filter(|&&byte| byte == b'\n').count() + 1;
+                    (line, record.iter().map(str::to_string).collect())
+                })
+                .map_err(|e| Error::BadRequest(format!("Invalid CSV: {}", e)))
+        })
+        .collect()
+}
+
+// 只读取第一个工作表
+fn read_xlsx(bytes: &[u8]) -> Result<Vec<Line>> {
+    let mut workbook = Xlsx::new(Cursor::new(bytes))
+        .map_err(|e| Error::BadRequest(format!("Invalid XLSX: {}", e)))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or(Error::BadRequest("XLSX has no worksheet".to_string()))?
+        .map_err(|e| Error::BadRequest(format!("Invalid XLSX: {}", e)))?;
+    // 工作表可能不从第一行开始
+    let first_row = range.start().map_or(0, |(row, _)| row as usize);
+    Ok(range
+        .rows()
+        .enumerate()
+        .map(|(index, row)| {
+            (
+                first_row + index + 1,
+                row.iter().map(|cell| cell.to_string()).collect(),
+            )
+        })
+        .collect())
+}
+
+pub fn parse_roster(
+    bytes: &[u8],
+    format: RosterFormat,
+) -> Result<Vec<Result<RosterRow, RowReport>>> {
+    let table = match format {
+        RosterFormat::Csv => read_csv(bytes)?,
+        RosterFormat::Xlsx => read_xlsx(bytes)?,
+    };
+    parse_rows(table)
+}
+
+// 按学号新建或更新学生, 按班级号查找或创建班级, 并加入班级
+async fn import_row<C>(row: &RosterRow, db: &C) -> Result<RowStatus>
+where
+    C: ConnectionTrait,
+{
+    let class = match class::Entity::find()
+        .filter(class::Column::ClassId.eq(row.class_id.clone()))
+        .one(db)
+        .await?
+    {
+        Some(class) => class,
+        None => {
+            class::ActiveModel::new(Some(row.class_id.clone()))
+                .insert(db)
+                .await?
+        }
+    };
+
+    let existing = student::Entity::find()
+        .filter(student::Column::StudentId.eq(row.student_id.clone()))
+        .one(db)
+        .await?;
+    let (student, status) = match existing {
+        // 已存在的学生不重置密码
+        Some(student) => {
+            let mut active: student::ActiveModel = student.into();
+            if let Some(name) = &row.name {
+                active.name = Set(Some(name.clone()));
+            }
+            if let Some(account) = &row.account {
+                active.account = Set(Some(account.clone()));
+            }
+            (active.update(db).await?, RowStatus::Updated)
+        }
+        None => {
+            let password = row
+                .password
+                .clone()
+                .unwrap_or_else(|| row.student_id.clone());
+            let mut student = student::ActiveModel::new_encrypted(
+                Some(row.student_id.clone()),
+                row.account.clone(),
+                password,
+                row.name.clone(),
+            )
+            .await?;
+            // 初始密码通常是学号, 首次登录后必须修改
+            student.must_change_password = Set(true);
+            let student = student.insert(db).await?;
+            (student, RowStatus::Created)
+        }
+    };
+
+    let joined = class_student_junction::Entity::find_by_id((class.id, student.id))
+        .one(db)
+        .await?;
+    if joined.is_none() {
+        student.join_class_with_db(class.id, db).await?;
+    }
+    Ok(status)
+}
+
+pub async fn import_roster_with_db<C>(
+    bytes: &[u8],
+    format: RosterFormat,
+    db: &C,
+) -> Result<ImportReport>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut report = ImportReport::default();
+    for row in parse_roster(bytes, format)? {
+        let row = match row {
+            Ok(row) => row,
+            Err(failed) => {
+                report.push(failed);
+                continue;
+            }
+        };
+        let txn = db.begin().await?;
+        let result = match import_row(&row, &txn).await {
+            Ok(status) => txn.commit().await.map(|_| status).map_err(Error::from),
+            Err(e) => Err(e),
+        };
+        report.push(match result {
+            Ok(status) => RowReport {
+                line: row.line,
+                student_id: Some(row.student_id),
+                status,
+                error: None,
+            },
+            Err(e) => RowReport {
+                line: row.line,
+                student_id: Some(row.student_id),
+                status: RowStatus::Failed,
+                error: Some(e.to_string()),
+            },
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use sea_orm::ModelTrait;
+
+    use super::*;
+    use crate::db::testing::{test_db, StudentBuilder};
+
+    #[test]
+    fn test_parse_csv_roster() {
+        let csv =
+            "学号,姓名,班级\n2021001,张三,计科2101\n,李四,计科2101\n\n2021003,王五,计科2102\n";
+        let rows = parse_roster(csv.as_bytes(), RosterFormat::Csv).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            Ok(RosterRow {
+                line: 2,
+                student_id: "2021001".to_string(),
+                class_id: "计科2101".to_string(),
+                name: Some("张三".to_string()),
+                account: None,
+                password: None,
+            })
+        );
+        assert_eq!(rows[1].as_ref().unwrap_err().line, 3);
+        assert_eq!(rows[2].as_ref().unwrap().line, 5);
+    }
+
+    #[test]
+    fn test_missing_required_column() {
+        let csv = "name,class_id\nfoo,bar\n";
+        assert!(parse_roster(csv.as_bytes(), RosterFormat::Csv).is_err());
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(RosterFormat::detect(b"PK\x03\x04rest"), RosterFormat::Xlsx);
+        assert_eq!(
+            RosterFormat::detect(b"student_id,class_id"),
+            RosterFormat::Csv
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_roster() {
+        let db = test_db().await;
+        let existing = StudentBuilder::new()
+            .password("keep-password")
+            .insert(&db)
+            .await;
+        let existing_id = existing.student_id.clone().unwrap();
+        let existing_account = existing.account.clone().unwrap();
+        let csv = format!(
+            "学号,姓名,班级,账号\n\
+             {existing_id},新名字,计科2101,\n\
+             2021901,王五,计科2101,\n\
+             2021901,王五,计科2102,\n\
+             ,无学号,计科2101,\n\
+             2021902,赵六,计科2101,{existing_account}\n"
+        );
+
+        let report = import_roster_with_db(csv.as_bytes(), RosterFormat::Csv, &db)
+            .await
+            .unwrap();
+        assert_eq!((report.created, report.updated, report.failed), (1, 2, 2));
+        let statuses: Vec<_> = report
+            .rows
+            .iter()
+            .map(|row| (row.line, row.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (2, RowStatus::Updated),
+                (3, RowStatus::Created),
+                (4, RowStatus::Updated),
+                (5, RowStatus::Failed),
+                // 账号与已有学生重复
+                (6, RowStatus::Failed),
+            ]
+        );
+
+        // 已有学生只更新资料, 不重置密码
+        let updated = student::Entity::find_by_id(existing.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.name.as_deref(), Some("新名字"));
+        assert!(!updated.must_change_password);
+        assert!(updated.verify_password("keep-password".to_string()).is_ok());
+
+        // 新学生以学号为初始密码, 重复的行把学生加入第二个班级
+        let created =
+            student::Model::find_by_student_id_or_account_with_db("2021901".to_string(), &db)
+                .await
+                .unwrap();
+        assert!(created.must_change_password);
+        assert!(created.verify_password("2021901".to_string()).is_ok());
+        let mut classes: Vec<_> = created
+            .find_related(class::Entity)
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|class| class.class_id)
+            .collect();
+        classes.sort();
+        assert_eq!(classes, vec!["计科2101", "计科2102"]);
+        assert!(
+            student::Model::find_by_student_id_or_account_with_db("2021902".to_string(), &db)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use crate::db::models::blackout_date::Column;
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, date, string},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BlackoutDateTable::BlackoutDate)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(date(Column::StartDate).not_null())
+                    .col(date(Column::EndDate).not_null())
+                    .col(string(Column::Reason).not_null())
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(BlackoutDateTable::BlackoutDate)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BlackoutDateTable {
+    #[sea_orm(iden = "blackout_date")]
+    BlackoutDate,
+}
@@ -0,0 +1,49 @@
+use crate::db::models::board::Column;
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, string, string_len, string_null},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BoardTable::Board)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(string(Column::Model).not_null())
+                    .col(string(Column::Serial).unique_key().not_null())
+                    .col(string_null(Column::Location))
+                    .col(string_len(Column::Status, 16).not_null())
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(BoardTable::Board)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum BoardTable {
+    Board,
+}
@@ -0,0 +1,50 @@
+use crate::db::models::calendar_feed::Column;
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, string, string_len, timestamp_with_time_zone},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CalendarFeedTable::CalendarFeed)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(string_len(Column::Owner, 16).not_null())
+                    .col(big_integer(Column::OwnerPid).not_null())
+                    .col(string(Column::TokenHash).unique_key().not_null())
+                    .col(timestamp_with_time_zone(Column::CreatedAt).not_null())
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(CalendarFeedTable::CalendarFeed)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum CalendarFeedTable {
+    #[sea_orm(iden = "calendar_feed")]
+    CalendarFeed,
+}
@@ -0,0 +1,55 @@
+use crate::db::models::experiment::Column;
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer_null, integer_null},
+};
+
+use super::experiment::ExperimentTable;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// sqlite每条ALTER TABLE只能添加一列
+fn columns() -> Vec<ColumnDef> {
+    vec![
+        big_integer_null(Column::FreeCancelHours),
+        integer_null(Column::LateCancelStrikes),
+        integer_null(Column::NoShowStrikes),
+    ]
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ExperimentTable::Experiment)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Column::FreeCancelHours,
+            Column::LateCancelStrikes,
+            Column::NoShowStrikes,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ExperimentTable::Experiment)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
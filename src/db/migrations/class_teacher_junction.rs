@@ -0,0 +1,70 @@
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, boolean},
+};
+
+use crate::db::models::{class, class_teacher_junction::Column, teacher};
+
+use super::{class::ClassTable, teacher::TeacherTable};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ClassTeacherJunctionTable::ClassTeacherJunction)
+                    .col(big_integer(Column::ClassPid).not_null())
+                    .col(big_integer(Column::TeacherPid).not_null())
+                    .col(boolean(Column::Admin).not_null().default(false))
+                    .primary_key(
+                        Index::create()
+                            .table(ClassTeacherJunctionTable::ClassTeacherJunction)
+                            .col(Column::ClassPid)
+                            .col(Column::TeacherPid),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                ClassTeacherJunctionTable::ClassTeacherJunction,
+                                Column::ClassPid,
+                            )
+                            .to(ClassTable::Class, class::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                ClassTeacherJunctionTable::ClassTeacherJunction,
+                                Column::TeacherPid,
+                            )
+                            .to(TeacherTable::Teacher, teacher::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ClassTeacherJunctionTable::ClassTeacherJunction)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ClassTeacherJunctionTable {
+    #[sea_orm(iden = "class_teacher_junction")]
+    ClassTeacherJunction,
+}
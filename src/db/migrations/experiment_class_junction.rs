@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use sea_orm_migration::{prelude::*, schema::big_integer};
+
+use crate::db::models::{class, experiment, experiment_class_junction::Column};
+
+use super::{class::ClassTable, experiment::ExperimentTable};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExperimentClassJunctionTable::ExperimentClassJunction)
+                    .col(big_integer(Column::ExperimentPid).not_null())
+                    .col(big_integer(Column::ClassPid).not_null())
+                    .primary_key(
+                        Index::create()
+                            .table(ExperimentClassJunctionTable::ExperimentClassJunction)
+                            .col(Column::ExperimentPid)
+                            .col(Column::ClassPid),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                ExperimentClassJunctionTable::ExperimentClassJunction,
+                                Column::ExperimentPid,
+                            )
+                            .to(ExperimentTable::Experiment, experiment::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                ExperimentClassJunctionTable::ExperimentClassJunction,
+                                Column::ClassPid,
+                            )
+                            .to(ClassTable::Class, class::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ExperimentClassJunctionTable::ExperimentClassJunction)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ExperimentClassJunctionTable {
+    #[sea_orm(iden = "experiment_class_junction")]
+    ExperimentClassJunction,
+}
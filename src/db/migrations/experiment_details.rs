@@ -0,0 +1,62 @@
+use crate::db::models::experiment::Column;
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{boolean, integer_null, string_null, text_null, timestamp_with_time_zone_null},
+};
+
+use super::experiment::ExperimentTable;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// sqlite每条ALTER TABLE只能添加一列
+fn columns() -> Vec<ColumnDef> {
+    vec![
+        text_null(Column::Description),
+        string_null(Column::BoardModel),
+        integer_null(Column::DurationMinutes),
+        timestamp_with_time_zone_null(Column::Deadline),
+        boolean(Column::Archived)
+            .not_null()
+            .default(false)
+            .to_owned(),
+    ]
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ExperimentTable::Experiment)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Column::Description,
+            Column::BoardModel,
+            Column::DurationMinutes,
+            Column::Deadline,
+            Column::Archived,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ExperimentTable::Experiment)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
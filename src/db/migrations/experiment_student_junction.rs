@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use sea_orm_migration::{prelude::*, schema::big_integer};
+
+use crate::db::models::{experiment, experiment_student_junction::Column, student};
+
+use super::{experiment::ExperimentTable, student::StudentTable};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExperimentStudentJunctionTable::ExperimentStudentJunction)
+                    .col(big_integer(Column::ExperimentPid).not_null())
+                    .col(big_integer(Column::StudentPid).not_null())
+                    .primary_key(
+                        Index::create()
+                            .table(ExperimentStudentJunctionTable::ExperimentStudentJunction)
+                            .col(Column::ExperimentPid)
+                            .col(Column::StudentPid),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                ExperimentStudentJunctionTable::ExperimentStudentJunction,
+                                Column::ExperimentPid,
+                            )
+                            .to(ExperimentTable::Experiment, experiment::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                ExperimentStudentJunctionTable::ExperimentStudentJunction,
+                                Column::StudentPid,
+                            )
+                            .to(StudentTable::Student, student::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ExperimentStudentJunctionTable::ExperimentStudentJunction)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ExperimentStudentJunctionTable {
+    #[sea_orm(iden = "experiment_student_junction")]
+    ExperimentStudentJunction,
+}
@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use sea_orm_migration::{prelude::*, schema::big_integer};
+
+use crate::db::models::{experiment, experiment_teacher_junction::Column, teacher};
+
+use super::{experiment::ExperimentTable, teacher::TeacherTable};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExperimentTeacherJunctionTable::ExperimentTeacherJunction)
+                    .col(big_integer(Column::ExperimentPid).not_null())
+                    .col(big_integer(Column::TeacherPid).not_null())
+                    .primary_key(
+                        Index::create()
+                            .table(ExperimentTeacherJunctionTable::ExperimentTeacherJunction)
+                            .col(Column::ExperimentPid)
+                            .col(Column::TeacherPid),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                ExperimentTeacherJunctionTable::ExperimentTeacherJunction,
+                                Column::ExperimentPid,
+                            )
+                            .to(ExperimentTable::Experiment, experiment::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                ExperimentTeacherJunctionTable::ExperimentTeacherJunction,
+                                Column::TeacherPid,
+                            )
+                            .to(TeacherTable::Teacher, teacher::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ExperimentTeacherJunctionTable::ExperimentTeacherJunction)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ExperimentTeacherJunctionTable {
+    #[sea_orm(iden = "experiment_teacher_junction")]
+    ExperimentTeacherJunction,
+}
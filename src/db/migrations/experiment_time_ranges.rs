@@ -0,0 +1,62 @@
+use crate::db::models::{experiment, experiment_time_ranges::Column};
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, integer, timestamp_with_time_zone},
+};
+
+use super::experiment::ExperimentTable;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExperimentTimeRangesTable::ExperimentTimeRanges)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(big_integer(Column::ExperimentPid).not_null())
+                    .col(timestamp_with_time_zone(Column::StartTime).not_null())
+                    .col(timestamp_with_time_zone(Column::EndTime).not_null())
+                    .col(integer(Column::Capacity).not_null())
+                    .col(integer(Column::Reserved).not_null().default(0))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                ExperimentTimeRangesTable::ExperimentTimeRanges,
+                                Column::ExperimentPid,
+                            )
+                            .to(ExperimentTable::Experiment, experiment::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ExperimentTimeRangesTable::ExperimentTimeRanges)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ExperimentTimeRangesTable {
+    #[sea_orm(iden = "experiment_time_ranges")]
+    ExperimentTimeRanges,
+}
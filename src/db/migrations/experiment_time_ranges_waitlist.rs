@@ -0,0 +1,74 @@
+use crate::db::models::{experiment_time_ranges, experiment_time_ranges_waitlist::Column, student};
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, timestamp_with_time_zone, timestamp_with_time_zone_null},
+};
+
+use super::{experiment_time_ranges::ExperimentTimeRangesTable, student::StudentTable};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WaitlistTable::ExperimentTimeRangesWaitlist)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(big_integer(Column::TimeRangePid).not_null())
+                    .col(big_integer(Column::StudentPid).not_null())
+                    .col(timestamp_with_time_zone(Column::CreatedAt).not_null())
+                    .col(timestamp_with_time_zone_null(Column::PromotedAt))
+                    .col(timestamp_with_time_zone_null(Column::AcknowledgedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                WaitlistTable::ExperimentTimeRangesWaitlist,
+                                Column::TimeRangePid,
+                            )
+                            .to(
+                                ExperimentTimeRangesTable::ExperimentTimeRanges,
+                                experiment_time_ranges::Column::Id,
+                            )
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                WaitlistTable::ExperimentTimeRangesWaitlist,
+                                Column::StudentPid,
+                            )
+                            .to(StudentTable::Student, student::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(WaitlistTable::ExperimentTimeRangesWaitlist)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum WaitlistTable {
+    #[sea_orm(iden = "experiment_time_ranges_waitlist")]
+    ExperimentTimeRangesWaitlist,
+}
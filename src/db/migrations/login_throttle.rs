@@ -0,0 +1,61 @@
+use crate::db::models::login_throttle::Column;
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{
+        big_integer, integer, string, string_len, timestamp_with_time_zone,
+        timestamp_with_time_zone_null,
+    },
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginThrottleTable::LoginThrottle)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(string_len(Column::Scope, 16).not_null())
+                    .col(string(Column::Key).not_null())
+                    .col(integer(Column::Failures).not_null().default(0))
+                    .col(timestamp_with_time_zone(Column::LastFailureAt).not_null())
+                    .col(timestamp_with_time_zone_null(Column::BlockedUntil))
+                    .index(
+                        Index::create()
+                            .name("idx_login_throttle_scope_key")
+                            .col(Column::Scope)
+                            .col(Column::Key)
+                            .unique(),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(LoginThrottleTable::LoginThrottle)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum LoginThrottleTable {
+    #[sea_orm(iden = "login_throttle")]
+    LoginThrottle,
+}
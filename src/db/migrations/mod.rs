@@ -1,15 +1,31 @@
+pub mod blackout_date;
+pub mod board;
+pub mod calendar_feed;
+pub mod cancellation_policy;
 pub mod class;
 pub mod class_student_junction;
 pub mod class_teacher_junction;
 pub mod experiment;
+pub mod experiment_class_junction;
+pub mod experiment_details;
 pub mod experiment_student_junction;
 pub mod experiment_teacher_junction;
 pub mod experiment_time_ranges;
 pub mod experiment_time_ranges_student_junction;
+pub mod experiment_time_ranges_waitlist;
+pub mod login_throttle;
+pub mod must_change_password;
+pub mod refresh_token_rotation;
+pub mod reservation_board;
 pub mod student;
 pub mod student_refresh_token;
+pub mod student_strike;
+pub mod student_suspension;
 pub mod teacher;
 pub mod teacher_refresh_token;
+pub mod teacher_role;
+pub mod term;
+pub mod time_range_series;
 
 use async_trait::async_trait;
 use sea_orm_migration::*;
@@ -33,6 +49,23 @@ impl MigratorTrait for Migrator {
             Box::new(experiment_time_ranges_student_junction::Migration),
             Box::new(class_student_junction::Migration),
             Box::new(class_teacher_junction::Migration),
+            // 之后的迁移按添加顺序追加在末尾, 不要插入到中间
+            Box::new(refresh_token_rotation::Migration),
+            Box::new(board::Migration),
+            Box::new(reservation_board::Migration),
+            Box::new(experiment_time_ranges_waitlist::Migration),
+            Box::new(teacher_role::Migration),
+            Box::new(must_change_password::Migration),
+            Box::new(login_throttle::Migration),
+            Box::new(experiment_details::Migration),
+            Box::new(experiment_class_junction::Migration),
+            Box::new(time_range_series::Migration),
+            Box::new(term::Migration),
+            Box::new(blackout_date::Migration),
+            Box::new(calendar_feed::Migration),
+            Box::new(student_strike::Migration),
+            Box::new(student_suspension::Migration),
+            Box::new(cancellation_policy::Migration),
         ]
     }
 }
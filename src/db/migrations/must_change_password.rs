@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use sea_orm_migration::{prelude::*, schema::boolean};
+
+use crate::db::models::{student, teacher};
+
+use super::{student::StudentTable, teacher::TeacherTable};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StudentTable::Student)
+                    .add_column(
+                        boolean(student::Column::MustChangePassword)
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TeacherTable::Teacher)
+                    .add_column(
+                        boolean(teacher::Column::MustChangePassword)
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StudentTable::Student)
+                    .drop_column(student::Column::MustChangePassword)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TeacherTable::Teacher)
+                    .drop_column(teacher::Column::MustChangePassword)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{string, timestamp_with_time_zone_null},
+};
+
+use crate::db::models::{student_refresh_token, teacher_refresh_token};
+
+use super::{
+    student_refresh_token::StudentRefreshTokenTable,
+    teacher_refresh_token::TeacherRefreshTokenTable,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StudentRefreshTokenTable::StudentRefreshToken)
+                    .add_column(
+                        string(student_refresh_token::Column::Family)
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StudentRefreshTokenTable::StudentRefreshToken)
+                    .add_column(timestamp_with_time_zone_null(
+                        student_refresh_token::Column::ConsumedAt,
+                    ))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TeacherRefreshTokenTable::TeacherRefreshToken)
+                    .add_column(
+                        string(teacher_refresh_token::Column::Family)
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TeacherRefreshTokenTable::TeacherRefreshToken)
+                    .add_column(timestamp_with_time_zone_null(
+                        teacher_refresh_token::Column::ConsumedAt,
+                    ))
+                    .to_owned(),
+            )
+            .await?;
+
+        // 已签发的令牌各自成为一个令牌族, 避免空族名把不同用户的令牌连在一起
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(StudentRefreshTokenTable::StudentRefreshToken)
+                    .value(
+                        student_refresh_token::Column::Family,
+                        Expr::col(student_refresh_token::Column::TokenHash),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(TeacherRefreshTokenTable::TeacherRefreshToken)
+                    .value(
+                        teacher_refresh_token::Column::Family,
+                        Expr::col(teacher_refresh_token::Column::TokenHash),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StudentRefreshTokenTable::StudentRefreshToken)
+                    .drop_column(student_refresh_token::Column::Family)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(StudentRefreshTokenTable::StudentRefreshToken)
+                    .drop_column(student_refresh_token::Column::ConsumedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TeacherRefreshTokenTable::TeacherRefreshToken)
+                    .drop_column(teacher_refresh_token::Column::Family)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TeacherRefreshTokenTable::TeacherRefreshToken)
+                    .drop_column(teacher_refresh_token::Column::ConsumedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
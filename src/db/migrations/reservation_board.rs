@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use sea_orm::DbBackend;
+use sea_orm_migration::{prelude::*, schema::big_integer_null};
+
+use crate::db::models::{board, experiment_time_ranges_student_junction::Column};
+
+use super::{
+    board::BoardTable, experiment_time_ranges_student_junction::TimeRangesStudentJunctionTable,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TimeRangesStudentJunctionTable::TimeRangesStudentJunction)
+                    .add_column(big_integer_null(Column::BoardPid))
+                    .to_owned(),
+            )
+            .await?;
+
+        // sqlite不支持给已有的表添加外键
+        if manager.get_database_backend() != DbBackend::Sqlite {
+            manager
+                .create_foreign_key(
+                    ForeignKey::create()
+                        .name("fk_reservation_board")
+                        .from(
+                            TimeRangesStudentJunctionTable::TimeRangesStudentJunction,
+                            Column::BoardPid,
+                        )
+                        .to(BoardTable::Board, board::Column::Id)
+                        .on_delete(ForeignKeyAction::SetNull)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // 同一时间段内一块开发板只能分配给一名学生
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_time_range_board")
+                    .table(TimeRangesStudentJunctionTable::TimeRangesStudentJunction)
+                    .col(Column::TimeRangePid)
+                    .col(Column::BoardPid)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_time_range_board")
+                    .table(TimeRangesStudentJunctionTable::TimeRangesStudentJunction)
+                    .to_owned(),
+            )
+            .await?;
+        if manager.get_database_backend() != DbBackend::Sqlite {
+            manager
+                .drop_foreign_key(
+                    ForeignKey::drop()
+                        .name("fk_reservation_board")
+                        .table(TimeRangesStudentJunctionTable::TimeRangesStudentJunction)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TimeRangesStudentJunctionTable::TimeRangesStudentJunction)
+                    .drop_column(Column::BoardPid)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,61 @@
+use crate::db::models::{student, student_refresh_token::Column};
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, string, timestamp_with_time_zone},
+};
+
+use super::student::StudentTable;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StudentRefreshTokenTable::StudentRefreshToken)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(big_integer(Column::StudentPid).not_null())
+                    .col(string(Column::TokenHash).unique_key().not_null())
+                    .col(timestamp_with_time_zone(Column::ExpiresAt).not_null())
+                    .col(timestamp_with_time_zone(Column::CreatedAt).not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StudentRefreshTokenTable::StudentRefreshToken,
+                                Column::StudentPid,
+                            )
+                            .to(StudentTable::Student, student::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(StudentRefreshTokenTable::StudentRefreshToken)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum StudentRefreshTokenTable {
+    #[sea_orm(iden = "student_refresh_token")]
+    StudentRefreshToken,
+}
@@ -0,0 +1,68 @@
+use crate::db::models::{experiment_time_ranges, student, student_strike::Column};
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, integer, string_len, timestamp_with_time_zone},
+};
+
+use super::{experiment_time_ranges::ExperimentTimeRangesTable, student::StudentTable};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StudentStrikeTable::StudentStrike)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(big_integer(Column::StudentPid).not_null())
+                    .col(big_integer(Column::TimeRangePid).not_null())
+                    .col(string_len(Column::Reason, 16).not_null())
+                    .col(integer(Column::Strikes).not_null())
+                    .col(timestamp_with_time_zone(Column::CreatedAt).not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(StudentStrikeTable::StudentStrike, Column::StudentPid)
+                            .to(StudentTable::Student, student::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(StudentStrikeTable::StudentStrike, Column::TimeRangePid)
+                            .to(
+                                ExperimentTimeRangesTable::ExperimentTimeRanges,
+                                experiment_time_ranges::Column::Id,
+                            )
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(StudentStrikeTable::StudentStrike)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum StudentStrikeTable {
+    #[sea_orm(iden = "student_strike")]
+    StudentStrike,
+}
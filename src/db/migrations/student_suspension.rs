@@ -0,0 +1,60 @@
+use crate::db::models::{student, student_suspension::Column};
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, timestamp_with_time_zone},
+};
+
+use super::student::StudentTable;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StudentSuspensionTable::StudentSuspension)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(big_integer(Column::StudentPid).not_null())
+                    .col(timestamp_with_time_zone(Column::CreatedAt).not_null())
+                    .col(timestamp_with_time_zone(Column::Until).not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                StudentSuspensionTable::StudentSuspension,
+                                Column::StudentPid,
+                            )
+                            .to(StudentTable::Student, student::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(StudentSuspensionTable::StudentSuspension)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum StudentSuspensionTable {
+    #[sea_orm(iden = "student_suspension")]
+    StudentSuspension,
+}
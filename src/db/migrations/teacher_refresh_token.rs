@@ -0,0 +1,61 @@
+use crate::db::models::{teacher, teacher_refresh_token::Column};
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, string, timestamp_with_time_zone},
+};
+
+use super::teacher::TeacherTable;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TeacherRefreshTokenTable::TeacherRefreshToken)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(big_integer(Column::TeacherPid).not_null())
+                    .col(string(Column::TokenHash).unique_key().not_null())
+                    .col(timestamp_with_time_zone(Column::ExpiresAt).not_null())
+                    .col(timestamp_with_time_zone(Column::CreatedAt).not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(
+                                TeacherRefreshTokenTable::TeacherRefreshToken,
+                                Column::TeacherPid,
+                            )
+                            .to(TeacherTable::Teacher, teacher::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(TeacherRefreshTokenTable::TeacherRefreshToken)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum TeacherRefreshTokenTable {
+    #[sea_orm(iden = "teacher_refresh_token")]
+    TeacherRefreshToken,
+}
@@ -0,0 +1,33 @@
+use crate::db::models::teacher::Column;
+use async_trait::async_trait;
+use sea_orm_migration::{prelude::*, schema::string_len};
+
+use super::teacher::TeacherTable;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TeacherTable::Teacher)
+                    .add_column(string_len(Column::Role, 16).not_null().default("teacher"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TeacherTable::Teacher)
+                    .drop_column(Column::Role)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,44 @@
+use crate::db::models::term::Column;
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, date, string},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TermTable::Term)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(string(Column::Name).not_null())
+                    .col(date(Column::StartDate).not_null())
+                    .col(date(Column::EndDate).not_null())
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TermTable::Term).if_exists().to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TermTable {
+    #[sea_orm(iden = "term")]
+    Term,
+}
@@ -0,0 +1,74 @@
+use crate::db::models::{experiment, experiment_time_ranges, time_range_series::Column};
+use async_trait::async_trait;
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, big_integer_null, json, timestamp_with_time_zone},
+};
+
+use super::{experiment::ExperimentTable, experiment_time_ranges::ExperimentTimeRangesTable};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TimeRangeSeriesTable::TimeRangeSeries)
+                    .col(
+                        big_integer(Column::Id)
+                            .auto_increment()
+                            .primary_key()
+                            .not_null(),
+                    )
+                    .col(big_integer(Column::ExperimentPid).not_null())
+                    .col(json(Column::Rule).not_null())
+                    .col(timestamp_with_time_zone(Column::CreatedAt).not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(TimeRangeSeriesTable::TimeRangeSeries, Column::ExperimentPid)
+                            .to(ExperimentTable::Experiment, experiment::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+        // 已有的时间段不属于任何系列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ExperimentTimeRangesTable::ExperimentTimeRanges)
+                    .add_column(big_integer_null(experiment_time_ranges::Column::SeriesPid))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ExperimentTimeRangesTable::ExperimentTimeRanges)
+                    .drop_column(experiment_time_ranges::Column::SeriesPid)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(TimeRangeSeriesTable::TimeRangeSeries)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TimeRangeSeriesTable {
+    #[sea_orm(iden = "time_range_series")]
+    TimeRangeSeries,
+}
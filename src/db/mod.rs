@@ -1,29 +1,134 @@
-use std::sync::LazyLock;
+use std::sync::OnceLock;
 
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher},
-    Argon2,
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier},
+    Argon2, Params, Version,
+};
+
+use crate::{
+    config::Argon2Config,
+    error::{Error, Result},
 };
-use jsonwebtoken::{Algorithm, Validation};
 
 pub mod api;
 pub mod db_conn;
+pub mod ical;
+pub mod import;
 pub mod migrations;
 pub mod models;
+pub mod rbac;
+pub mod services;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod token;
+
+// 启动时由配置设置, 未设置时使用argon2的默认参数
+static ARGON2_PARAMS: OnceLock<Params> = OnceLock::new();
 
-pub static ARGON2: LazyLock<Argon2<'_>> = LazyLock::new(|| Argon2::default());
-pub static JWT_VALIDATION: LazyLock<Validation> =
-    LazyLock::new(|| Validation::new(Algorithm::HS256));
+pub fn init_argon2(config: &Argon2Config) -> anyhow::Result<()> {
+    ARGON2_PARAMS
+        .set(config.params()?)
+        .map_err(|_| anyhow::anyhow!("Argon2 parameters are already initialized"))
+}
+
+pub fn argon2() -> Argon2<'static> {
+    Argon2::new(
+        argon2::Algorithm::Argon2id,
+        Version::V0x13,
+        ARGON2_PARAMS.get().cloned().unwrap_or_default(),
+    )
+}
 
-pub async fn hash_password(password: String) -> Result<String, String> {
+// 哈希的算法, 版本或参数与当前配置不一致时需要重新哈希
+pub fn password_needs_rehash(password_hash: &str) -> bool {
+    let Ok(hash) = PasswordHash::new(password_hash) else {
+        return true;
+    };
+    let current = ARGON2_PARAMS.get().cloned().unwrap_or_default();
+    let outdated_params = match Params::try_from(&hash) {
+        Ok(params) => {
+            params.m_cost() != current.m_cost()
+                || params.t_cost() != current.t_cost()
+                || params.p_cost() != current.p_cost()
+        }
+        Err(_) => true,
+    };
+    hash.algorithm != argon2::Algorithm::Argon2id.ident()
+        || hash.version != Some(u32::from(Version::V0x13))
+        || outdated_params
+}
+
+// 账号不存在时用于校验的哈希, 首次使用时按当前参数生成
+static DUMMY_PASSWORD_HASH: OnceLock<String> = OnceLock::new();
+
+// 账号不存在时仍完成一次同等开销的哈希校验, 避免通过响应时间判断账号是否存在
+pub fn reject_unknown_account(password: &str) -> Error {
+    let dummy = DUMMY_PASSWORD_HASH.get_or_init(|| {
+        let salt_string = argon2::password_hash::SaltString::generate(&mut OsRng);
+        argon2()
+            .hash_password(b"unknown account", &salt_string)
+            .map(|password_hash| password_hash.to_string())
+            .unwrap_or_default()
+    });
+    if let Ok(dummy) = PasswordHash::new(dummy) {
+        let _ = argon2().verify_password(password.as_bytes(), &dummy);
+    }
+    Error::InvalidCredentials
+}
+
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+pub fn validate_new_password(password: &str) -> Result<()> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(Error::BadRequest(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        )));
+    }
+    Ok(())
+}
+
+pub async fn hash_password(password: String) -> Result<String> {
     tokio::task::spawn_blocking(move || {
         let salt_string = argon2::password_hash::SaltString::generate(&mut OsRng);
         let password_clone = password.to_string();
-        ARGON2
+        argon2()
             .hash_password(password_clone.as_bytes(), &salt_string)
-            .map_err(|e| format!("Failed to hash password: {:?}", e))
+            .map_err(Error::internal)
             .map(|password_hash| password_hash.to_string())
     })
     .await
-    .map_err(|e| format!("Failed to join blocking task of hash password: {:?}", e))?
+    .map_err(Error::internal)?
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_password_needs_rehash() {
+        let current = hash_password("password".to_string()).await.unwrap();
+        assert!(!password_needs_rehash(&current));
+
+        // 使用更低的参数生成的旧哈希
+        let weak = Argon2::new(
+            argon2::Algorithm::Argon2id,
+            Version::V0x13,
+            Params::new(Params::MIN_M_COST, 1, 1, None).unwrap(),
+        );
+        let salt = argon2::password_hash::SaltString::generate(&mut OsRng);
+        let outdated = weak.hash_password(b"password", &salt).unwrap().to_string();
+        assert!(password_needs_rehash(&outdated));
+        assert!(password_needs_rehash("not a hash"));
+    }
+
+    #[test]
+    fn test_reject_unknown_account() {
+        for password in ["unknown account", "password"] {
+            assert!(matches!(
+                reject_unknown_account(password),
+                Error::InvalidCredentials
+            ));
+        }
+    }
 }
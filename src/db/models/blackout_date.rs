@@ -0,0 +1,147 @@
+// 不开放预约的日期段, 如法定节假日和考试周
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "blackout_date")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 第一天
+    pub start_date: NaiveDate,
+    // 最后一天, 包含当天
+    pub end_date: NaiveDate,
+    // 原因, 如"国庆节"
+    pub reason: String,
+}
+
+impl Model {
+    // 与[from, to]有交集的日期段
+    pub async fn list_between_with_db<C>(
+        from: NaiveDate,
+        to: NaiveDate,
+        db: &C,
+    ) -> Result<Vec<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .filter(Column::StartDate.lte(to))
+            .filter(Column::EndDate.gte(from))
+            .order_by_asc(Column::StartDate)
+            .all(db)
+            .await?)
+    }
+
+    // [from, to]中所有不开放的日期
+    pub async fn dates_between_with_db<C>(
+        from: NaiveDate,
+        to: NaiveDate,
+        db: &C,
+    ) -> Result<HashSet<NaiveDate>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Self::list_between_with_db(from, to, db)
+            .await?
+            .iter()
+            .flat_map(|blackout| {
+                blackout
+                    .start_date
+                    .max(from)
+                    .iter_days()
+                    .take_while(move |date| *date <= blackout.end_date.min(to))
+            })
+            .collect())
+    }
+
+    // 某个时刻按当地日期是否落在不开放的日期段中
+    pub async fn covering_with_db<C>(
+        time: DateTime<Utc>,
+        tz: FixedOffset,
+        db: &C,
+    ) -> Result<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        let date = time.with_timezone(&tz).date_naive();
+        Ok(Self::list_between_with_db(date, date, db)
+            .await?
+            .into_iter()
+            .next())
+    }
+}
+
+impl ActiveModel {
+    pub fn new(start_date: NaiveDate, end_date: NaiveDate, reason: String) -> Result<Self> {
+        if start_date > end_date {
+            return Err(Error::BadRequest(
+                "Start date must not be later than end date".to_string(),
+            ));
+        }
+        if reason.trim().is_empty() {
+            return Err(Error::BadRequest(
+                "Blackout reason must not be empty".to_string(),
+            ));
+        }
+        Ok(Self {
+            id: NotSet,
+            start_date: Set(start_date),
+            end_date: Set(end_date),
+            reason: Set(reason.trim().to_string()),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No relations")
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::testing::test_db;
+
+    #[tokio::test]
+    async fn test_dates_between() {
+        let db = test_db().await;
+        let date = |day| NaiveDate::from_ymd_opt(2024, 10, day).unwrap();
+        ActiveModel::new(date(1), date(7), "国庆节".to_string())
+            .unwrap()
+            .insert(&db)
+            .await
+            .unwrap();
+
+        let dates = Model::dates_between_with_db(date(6), date(10), &db)
+            .await
+            .unwrap();
+        assert_eq!(dates, HashSet::from([date(6), date(7)]));
+
+        // 东八区10月8日零点前仍是10月7日
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let time = "2024-10-07T15:59:00Z".parse().unwrap();
+        assert!(Model::covering_with_db(time, tz, &db)
+            .await
+            .unwrap()
+            .is_some());
+        let time = "2024-10-07T16:00:00Z".parse().unwrap();
+        assert!(Model::covering_with_db(time, tz, &db)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,77 @@
+// 实验室中的FPGA开发板
+
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, Set};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum BoardStatus {
+    // 可用
+    #[sea_orm(string_value = "available")]
+    Available,
+    // 损坏
+    #[sea_orm(string_value = "broken")]
+    Broken,
+    // 维护中
+    #[sea_orm(string_value = "maintenance")]
+    Maintenance,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "board")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 型号
+    pub model: String,
+    // 序列号
+    #[sea_orm(unique)]
+    pub serial: String,
+    // 位置(工位)
+    pub location: Option<String>,
+    // 状态
+    pub status: BoardStatus,
+}
+
+impl Model {
+    pub fn is_available(&self) -> bool {
+        self.status == BoardStatus::Available
+    }
+}
+
+impl ActiveModel {
+    pub fn new(model: String, serial: String, location: Option<String>) -> Self {
+        Self {
+            id: NotSet,
+            model: Set(model),
+            serial: Set(serial),
+            location: Set(location),
+            status: Set(BoardStatus::Available),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Reservations,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Reservations => {
+                Entity::has_many(super::experiment_time_ranges_student_junction::Entity).into()
+            }
+        }
+    }
+}
+
+impl Related<super::experiment_time_ranges_student_junction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Reservations.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,130 @@
+// 日历订阅令牌, 持有令牌即可只读访问对应用户的预约日历
+// 与刷新令牌一样, 数据库中只保存令牌的哈希
+
+use chrono::Utc;
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::token::{generate_refresh_token, hash_refresh_token},
+    error::{Error, Result},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum FeedOwner {
+    #[sea_orm(string_value = "student")]
+    Student,
+    #[sea_orm(string_value = "teacher")]
+    Teacher,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "calendar_feed")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 订阅者类型
+    pub owner: FeedOwner,
+    // 学生或教师主键
+    pub owner_pid: i64,
+    // 令牌的sha256哈希
+    #[sea_orm(unique)]
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    // 创建时间
+    pub created_at: DateTimeUtc,
+}
+
+impl Model {
+    // 生成新的订阅令牌并吊销旧令牌, 每个用户同时只有一个有效的订阅地址
+    pub async fn regenerate_with_db<C>(
+        owner: FeedOwner,
+        owner_pid: i64,
+        db: &C,
+    ) -> Result<(String, Self)>
+    where
+        C: ConnectionTrait,
+    {
+        Self::revoke_with_db(owner, owner_pid, db).await?;
+        let token = generate_refresh_token();
+        let model = ActiveModel {
+            id: NotSet,
+            owner: Set(owner),
+            owner_pid: Set(owner_pid),
+            token_hash: Set(hash_refresh_token(&token)),
+            created_at: Set(Utc::now()),
+        }
+        .insert(db)
+        .await?;
+        Ok((token, model))
+    }
+
+    pub async fn revoke_with_db<C>(owner: FeedOwner, owner_pid: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::delete_many()
+            .filter(Column::Owner.eq(owner))
+            .filter(Column::OwnerPid.eq(owner_pid))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_token_with_db<C>(token: &str, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::find()
+            .filter(Column::TokenHash.eq(hash_refresh_token(token)))
+            .one(db)
+            .await?
+            .ok_or(Error::not_found("Calendar feed"))
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No relations")
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::testing::test_db;
+
+    #[tokio::test]
+    async fn test_regenerate_revokes_old_token() {
+        let db = test_db().await;
+        let (old, _) = Model::regenerate_with_db(FeedOwner::Student, 1, &db)
+            .await
+            .unwrap();
+        let (new, feed) = Model::regenerate_with_db(FeedOwner::Student, 1, &db)
+            .await
+            .unwrap();
+        assert!(matches!(
+            Model::find_by_token_with_db(&old, &db).await,
+            Err(Error::NotFound(_))
+        ));
+        assert_eq!(Model::find_by_token_with_db(&new, &db).await.unwrap(), feed);
+
+        // 教师的订阅不受影响
+        let (teacher, _) = Model::regenerate_with_db(FeedOwner::Teacher, 1, &db)
+            .await
+            .unwrap();
+        Model::revoke_with_db(FeedOwner::Student, 1, &db)
+            .await
+            .unwrap();
+        assert!(Model::find_by_token_with_db(&new, &db).await.is_err());
+        assert!(Model::find_by_token_with_db(&teacher, &db).await.is_ok());
+    }
+}
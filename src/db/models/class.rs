@@ -37,9 +37,7 @@ pub enum Relation {}
 
 impl RelationTrait for Relation {
     fn def(&self) -> RelationDef {
-        match self {
-            _ => panic!("unreachable"),
-        }
+        match *self {}
     }
 }
 
@@ -69,4 +67,18 @@ impl Related<super::teacher::Entity> for Entity {
     }
 }
 
+impl Related<super::experiment::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::experiment_class_junction::Relation::Experiment.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(
+            super::experiment_class_junction::Relation::Class
+                .def()
+                .rev(),
+        )
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,107 @@
+// class和teacher的多对多关系 连接表
+
+use sea_orm::{entity::prelude::*, Set};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Default, Debug, DeriveEntity)]
+pub struct Entity;
+
+impl EntityName for Entity {
+    fn table_name(&self) -> &str {
+        "class_teacher_junction"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveModel, DeriveActiveModel, Serialize, Deserialize)]
+pub struct Model {
+    pub class_pid: i64,
+    pub teacher_pid: i64,
+    // 是否为班级管理员
+    pub admin: bool,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+pub enum Column {
+    ClassPid,
+    TeacherPid,
+    Admin,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
+pub enum PrimaryKey {
+    ClassPid,
+    TeacherPid,
+}
+
+impl PrimaryKeyTrait for PrimaryKey {
+    type ValueType = (i64, i64);
+
+    fn auto_increment() -> bool {
+        false
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Class,
+    Teacher,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Class => Entity::belongs_to(super::class::Entity)
+                .from(Column::ClassPid)
+                .to(super::class::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+            Relation::Teacher => Entity::belongs_to(super::teacher::Entity)
+                .from(Column::TeacherPid)
+                .to(super::teacher::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::teacher::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Teacher.def()
+    }
+}
+
+impl Related<super::class::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Class.def()
+    }
+}
+
+impl ColumnTrait for Column {
+    type EntityName = Entity;
+
+    fn def(&self) -> ColumnDef {
+        match self {
+            Column::ClassPid => {
+                sea_orm::prelude::ColumnTypeTrait::def(sea_orm::prelude::ColumnType::Integer)
+            }
+            Column::TeacherPid => {
+                sea_orm::prelude::ColumnTypeTrait::def(sea_orm::prelude::ColumnType::Integer)
+            }
+            Column::Admin => {
+                sea_orm::prelude::ColumnTypeTrait::def(sea_orm::prelude::ColumnType::Boolean)
+            }
+        }
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl ActiveModel {
+    pub fn new(class_pid: i64, teacher_pid: i64, admin: bool) -> Self {
+        Self {
+            class_pid: Set(class_pid),
+            teacher_pid: Set(teacher_pid),
+            admin: Set(admin),
+        }
+    }
+}
@@ -0,0 +1,349 @@
+// 实验
+
+use chrono::{Duration, Utc};
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+
+use super::experiment_teacher_junction;
+use crate::{
+    config::ReservationConfig,
+    error::{Error, Result},
+};
+
+#[derive(Default, Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "experiment")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 实验名称
+    pub title: String,
+    // 实验说明
+    pub description: Option<String>,
+    // 需要的开发板型号, 对应board.model, 为空表示不限
+    pub board_model: Option<String>,
+    // 单次实验时长(分钟)
+    pub duration_minutes: Option<i32>,
+    // 预约截止时间
+    pub deadline: Option<DateTimeUtc>,
+    // 已归档的实验不再出现在默认列表中, 也不能修改
+    pub archived: bool,
+    // 以下取消规则为空时使用全局配置
+    // 开始前多少小时之前可以免费取消
+    pub free_cancel_hours: Option<i64>,
+    // 逾期取消记录的违约次数
+    pub late_cancel_strikes: Option<i32>,
+    // 缺席记录的违约次数
+    pub no_show_strikes: Option<i32>,
+}
+
+// 合并全局配置和实验设置后的取消规则
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CancellationPolicy {
+    pub free_cancel_hours: i64,
+    pub late_cancel_strikes: i32,
+    pub no_show_strikes: i32,
+}
+
+impl CancellationPolicy {
+    // 在免费取消期限之后取消视为逾期
+    pub fn is_late(&self, start_time: DateTimeUtc, now: DateTimeUtc) -> bool {
+        start_time - now < Duration::hours(self.free_cancel_hours)
+    }
+}
+
+// 创建或修改实验时可以设置的字段
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExperimentFields {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub board_model: Option<String>,
+    #[serde(default)]
+    pub duration_minutes: Option<i32>,
+    #[serde(default)]
+    pub deadline: Option<DateTimeUtc>,
+    #[serde(default)]
+    pub free_cancel_hours: Option<i64>,
+    #[serde(default)]
+    pub late_cancel_strikes: Option<i32>,
+    #[serde(default)]
+    pub no_show_strikes: Option<i32>,
+}
+
+impl ExperimentFields {
+    pub const MAX_TITLE_LEN: usize = 128;
+
+    pub fn validate(&self) -> Result<()> {
+        let title = self.title.trim();
+        if title.is_empty() {
+            return Err(Error::BadRequest(
+                "Experiment title must not be empty".to_string(),
+            ));
+        }
+        if title.chars().count() > Self::MAX_TITLE_LEN {
+            return Err(Error::BadRequest(format!(
+                "Experiment title must not exceed {} characters",
+                Self::MAX_TITLE_LEN
+            )));
+        }
+        if matches!(&self.board_model, Some(board_model) if board_model.trim().is_empty()) {
+            return Err(Error::BadRequest(
+                "Board model must not be empty".to_string(),
+            ));
+        }
+        if matches!(self.duration_minutes, Some(duration) if duration <= 0) {
+            return Err(Error::BadRequest(
+                "Experiment duration must be positive".to_string(),
+            ));
+        }
+        if [self.late_cancel_strikes, self.no_show_strikes]
+            .into_iter()
+            .flatten()
+            .any(|strikes| strikes < 0)
+            || matches!(self.free_cancel_hours, Some(hours) if hours < 0)
+        {
+            return Err(Error::BadRequest(
+                "Cancellation rules must not be negative".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Model {
+    pub async fn find_by_id_with_db<C>(id: i64, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(Error::not_found("Experiment"))
+    }
+
+    // 所有实验, 默认不包含已归档的实验
+    pub async fn list_with_db<C>(include_archived: bool, db: &C) -> Result<Vec<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        let mut query = Entity::find().order_by_asc(Column::Id);
+        if !include_archived {
+            query = query.filter(Column::Archived.eq(false));
+        }
+        Ok(query.all(db).await?)
+    }
+
+    // 教师负责的实验
+    pub async fn list_by_teacher_with_db<C>(
+        teacher_pid: i64,
+        include_archived: bool,
+        db: &C,
+    ) -> Result<Vec<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        let mut query = Entity::find()
+            .inner_join(experiment_teacher_junction::Entity)
+            .filter(experiment_teacher_junction::Column::TeacherPid.eq(teacher_pid))
+            .order_by_asc(Column::Id);
+        if !include_archived {
+            query = query.filter(Column::Archived.eq(false));
+        }
+        Ok(query.all(db).await?)
+    }
+
+    pub async fn is_owned_by_with_db<C>(&self, teacher_pid: i64, db: &C) -> Result<bool>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(
+            experiment_teacher_junction::Entity::find_by_id((self.id, teacher_pid))
+                .one(db)
+                .await?
+                .is_some(),
+        )
+    }
+
+    pub fn cancellation_policy(&self, config: &ReservationConfig) -> CancellationPolicy {
+        CancellationPolicy {
+            free_cancel_hours: self.free_cancel_hours.unwrap_or(config.free_cancel_hours),
+            late_cancel_strikes: self
+                .late_cancel_strikes
+                .unwrap_or(config.late_cancel_strikes),
+            no_show_strikes: self.no_show_strikes.unwrap_or(config.no_show_strikes),
+        }
+    }
+
+    // 截止时间之后不能再预约
+    pub fn is_open(&self) -> bool {
+        !self.archived && self.deadline.is_none_or(|deadline| deadline > Utc::now())
+    }
+
+    pub async fn update_with_db<C>(self, fields: ExperimentFields, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        if self.archived {
+            return Err(Error::Conflict(
+                "Archived experiment cannot be modified".to_string(),
+            ));
+        }
+        fields.validate()?;
+        let mut active: ActiveModel = self.into();
+        active.set_fields(fields);
+        Ok(active.update(db).await?)
+    }
+
+    pub async fn set_archived_with_db<C>(self, archived: bool, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let mut active: ActiveModel = self.into();
+        active.archived = Set(archived);
+        Ok(active.update(db).await?)
+    }
+
+    // 添加负责教师
+    pub async fn add_owner_with_db<C>(&self, teacher_pid: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        if self.is_owned_by_with_db(teacher_pid, db).await? {
+            return Err(Error::Conflict(
+                "Teacher already owns the experiment".to_string(),
+            ));
+        }
+        experiment_teacher_junction::ActiveModel::new(self.id, teacher_pid)
+            .insert(db)
+            .await?;
+        Ok(())
+    }
+
+    // 移除负责教师, 实验至少保留一名负责教师
+    pub async fn remove_owner_with_db<C>(&self, teacher_pid: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let owners = experiment_teacher_junction::Entity::find()
+            .filter(experiment_teacher_junction::Column::ExperimentPid.eq(self.id))
+            .count(db)
+            .await?;
+        if !self.is_owned_by_with_db(teacher_pid, db).await? {
+            return Err(Error::not_found("Experiment owner"));
+        }
+        if owners <= 1 {
+            return Err(Error::Conflict(
+                "Experiment must keep at least one owner".to_string(),
+            ));
+        }
+        experiment_teacher_junction::Entity::delete_by_id((self.id, teacher_pid))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+}
+
+impl ActiveModel {
+    pub fn new(title: String) -> Self {
+        Self::from_fields(ExperimentFields {
+            title,
+            ..Default::default()
+        })
+    }
+
+    pub fn from_fields(fields: ExperimentFields) -> Self {
+        let mut active = Self {
+            id: NotSet,
+            archived: Set(false),
+            ..Default::default()
+        };
+        active.set_fields(fields);
+        active
+    }
+
+    fn set_fields(&mut self, fields: ExperimentFields) {
+        self.title = Set(fields.title.trim().to_string());
+        self.description = Set(fields.description);
+        self.board_model = Set(fields.board_model);
+        self.duration_minutes = Set(fields.duration_minutes);
+        self.deadline = Set(fields.deadline);
+        self.free_cancel_hours = Set(fields.free_cancel_hours);
+        self.late_cancel_strikes = Set(fields.late_cancel_strikes);
+        self.no_show_strikes = Set(fields.no_show_strikes);
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    TimeRanges,
+    TeacherJunction,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::TimeRanges => Entity::has_many(super::experiment_time_ranges::Entity).into(),
+            Relation::TeacherJunction => {
+                Entity::has_many(super::experiment_teacher_junction::Entity).into()
+            }
+        }
+    }
+}
+
+impl Related<super::experiment_time_ranges::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TimeRanges.def()
+    }
+}
+
+impl Related<super::experiment_teacher_junction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TeacherJunction.def()
+    }
+}
+
+impl Related<super::teacher::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::experiment_teacher_junction::Relation::Teacher.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(
+            super::experiment_teacher_junction::Relation::Experiment
+                .def()
+                .rev(),
+        )
+    }
+}
+
+impl Related<super::class::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::experiment_class_junction::Relation::Class.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(
+            super::experiment_class_junction::Relation::Experiment
+                .def()
+                .rev(),
+        )
+    }
+}
+
+impl Related<super::student::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::experiment_student_junction::Relation::Student.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(
+            super::experiment_student_junction::Relation::Experiment
+                .def()
+                .rev(),
+        )
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,100 @@
+// experiment和class的多对多关系 连接表, 表示实验布置给了整个班级
+
+use sea_orm::{entity::prelude::*, Set};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Default, Debug, DeriveEntity)]
+pub struct Entity;
+
+impl EntityName for Entity {
+    fn table_name(&self) -> &str {
+        "experiment_class_junction"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveModel, DeriveActiveModel, Serialize, Deserialize)]
+pub struct Model {
+    pub experiment_pid: i64,
+    pub class_pid: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+pub enum Column {
+    ExperimentPid,
+    ClassPid,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
+pub enum PrimaryKey {
+    ExperimentPid,
+    ClassPid,
+}
+
+impl PrimaryKeyTrait for PrimaryKey {
+    type ValueType = (i64, i64);
+
+    fn auto_increment() -> bool {
+        false
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Experiment,
+    Class,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Experiment => Entity::belongs_to(super::experiment::Entity)
+                .from(Column::ExperimentPid)
+                .to(super::experiment::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+            Relation::Class => Entity::belongs_to(super::class::Entity)
+                .from(Column::ClassPid)
+                .to(super::class::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::class::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Class.def()
+    }
+}
+
+impl Related<super::experiment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Experiment.def()
+    }
+}
+
+impl ColumnTrait for Column {
+    type EntityName = Entity;
+
+    fn def(&self) -> ColumnDef {
+        match self {
+            Column::ExperimentPid => {
+                sea_orm::prelude::ColumnTypeTrait::def(sea_orm::prelude::ColumnType::Integer)
+            }
+            Column::ClassPid => {
+                sea_orm::prelude::ColumnTypeTrait::def(sea_orm::prelude::ColumnType::Integer)
+            }
+        }
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl ActiveModel {
+    pub fn new(experiment_pid: i64, class_pid: i64) -> Self {
+        Self {
+            experiment_pid: Set(experiment_pid),
+            class_pid: Set(class_pid),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+// experiment和student的多对多关系 连接表, 表示学生选修了该实验
+
+use sea_orm::{entity::prelude::*, sea_query::OnConflict, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+#[derive(Copy, Clone, Default, Debug, DeriveEntity)]
+pub struct Entity;
+
+impl EntityName for Entity {
+    fn table_name(&self) -> &str {
+        "experiment_student_junction"
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveModel, DeriveActiveModel, Serialize, Deserialize)]
+pub struct Model {
+    pub experiment_pid: i64,
+    pub student_pid: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+pub enum Column {
+    ExperimentPid,
+    StudentPid,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
+pub enum PrimaryKey {
+    ExperimentPid,
+    StudentPid,
+}
+
+impl PrimaryKeyTrait for PrimaryKey {
+    type ValueType = (i64, i64);
+
+    fn auto_increment() -> bool {
+        false
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Experiment,
+    Student,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Experiment => Entity::belongs_to(super::experiment::Entity)
+                .from(Column::ExperimentPid)
+                .to(super::experiment::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+            Relation::Student => Entity::belongs_to(super::student::Entity)
+                .from(Column::StudentPid)
+                .to(super::student::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::student::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Student.def()
+    }
+}
+
+impl Related<super::experiment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Experiment.def()
+    }
+}
+
+impl ColumnTrait for Column {
+    type EntityName = Entity;
+
+    fn def(&self) -> ColumnDef {
+        match self {
+            Column::ExperimentPid => {
+                sea_orm::prelude::ColumnTypeTrait::def(sea_orm::prelude::ColumnType::Integer)
+            }
+            Column::StudentPid => {
+                sea_orm::prelude::ColumnTypeTrait::def(sea_orm::prelude::ColumnType::Integer)
+            }
+        }
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    // 批量选修, 已经选修的学生会被跳过
+    pub async fn enroll_many_with_db<C>(
+        experiment_pid: i64,
+        student_pids: impl IntoIterator<Item = i64>,
+        db: &C,
+    ) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::insert_many(
+            student_pids
+                .into_iter()
+                .map(|student_pid| ActiveModel::new(experiment_pid, student_pid)),
+        )
+        .on_conflict(
+            OnConflict::columns([Column::ExperimentPid, Column::StudentPid])
+                .do_nothing()
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await?;
+        Ok(())
+    }
+}
+
+impl ActiveModel {
+    pub fn new(experiment_pid: i64, student_pid: i64) -> Self {
+        Self {
+            experiment_pid: Set(experiment_pid),
+            student_pid: Set(student_pid),
+        }
+    }
+}
@@ -0,0 +1,387 @@
+// 实验的可预约时间段, 每个时间段有人数上限
+
+use chrono::Utc;
+use sea_orm::{entity::prelude::*, sea_query::Expr, ActiveValue::NotSet, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    blackout_date, experiment, experiment_student_junction,
+    experiment_time_ranges_student_junction, experiment_time_ranges_waitlist, student_suspension,
+};
+use crate::{
+    config::ReservationConfig,
+    error::{Error, Result},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "experiment_time_ranges")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 实验主键
+    pub experiment_pid: i64,
+    // 开始时间
+    pub start_time: DateTimeUtc,
+    // 结束时间
+    pub end_time: DateTimeUtc,
+    // 人数上限
+    pub capacity: i32,
+    // 已预约人数
+    pub reserved: i32,
+    // 批量生成时所属的系列
+    pub series_pid: Option<i64>,
+}
+
+impl Model {
+    pub async fn find_by_id_with_db<C>(id: i64, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(Error::not_found("Time range"))
+    }
+
+    pub fn remaining(&self) -> i32 {
+        (self.capacity - self.reserved).max(0)
+    }
+
+    // 学生预约该时间段, 只能预约自己选修的实验
+    pub async fn reserve_with_db<C>(
+        &self,
+        student_pid: i64,
+        config: &ReservationConfig,
+        db: &C,
+    ) -> Result<experiment_time_ranges_student_junction::Model>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        if self.start_time <= Utc::now() {
+            return Err(Error::BadRequest(
+                "Time range has already started".to_string(),
+            ));
+        }
+        // 已归档或已过预约截止时间的实验不能再预约
+        if !experiment::Model::find_by_id_with_db(self.experiment_pid, db)
+            .await?
+            .is_open()
+        {
+            return Err(Error::BadRequest(
+                "Experiment is closed for reservation".to_string(),
+            ));
+        }
+        if let Some(blackout) =
+            blackout_date::Model::covering_with_db(self.start_time, config.timezone(), db).await?
+        {
+            return Err(Error::BadRequest(format!(
+                "Time range falls on a blackout date: {}",
+                blackout.reason
+            )));
+        }
+        self.ensure_student_eligible_with_db(student_pid, db)
+            .await?;
+
+        let txn = db.begin().await?;
+        if !Self::occupy_seat_with_db(self.id, &txn).await? {
+            return Err(Error::Conflict("Time range is full".to_string()));
+        }
+        // 重复预约会触发主键冲突, 事务回滚后占位也随之撤销
+        let reservation =
+            experiment_time_ranges_student_junction::ActiveModel::new(self.id, student_pid)
+                .insert(&txn)
+                .await?;
+        txn.commit().await?;
+        Ok(reservation)
+    }
+
+    // 学生可以占用该时间段的名额: 已选修实验且不在暂停预约期间
+    // 直接预约, 加入候补和候补递补都需要检查
+    pub(crate) async fn ensure_student_eligible_with_db<C>(
+        &self,
+        student_pid: i64,
+        db: &C,
+    ) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let enrolled =
+            experiment_student_junction::Entity::find_by_id((self.experiment_pid, student_pid))
+                .one(db)
+                .await?;
+        if enrolled.is_none() {
+            return Err(Error::Forbidden(
+                "Student is not enrolled in the experiment".to_string(),
+            ));
+        }
+        student_suspension::Model::ensure_not_suspended_with_db(student_pid, db).await
+    }
+
+    // 条件更新占位, 并发预约时不会超过人数上限, 返回是否占位成功
+    pub(crate) async fn occupy_seat_with_db<C>(id: i64, db: &C) -> Result<bool>
+    where
+        C: ConnectionTrait,
+    {
+        let occupied = Entity::update_many()
+            .col_expr(Column::Reserved, Expr::col(Column::Reserved).add(1))
+            .filter(Column::Id.eq(id))
+            .filter(Expr::col(Column::Reserved).lt(Expr::col(Column::Capacity)))
+            .exec(db)
+            .await?;
+        Ok(occupied.rows_affected == 1)
+    }
+
+    pub(crate) async fn release_seat_with_db<C>(id: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::update_many()
+            .col_expr(Column::Reserved, Expr::col(Column::Reserved).sub(1))
+            .filter(Column::Id.eq(id))
+            .filter(Column::Reserved.gt(0))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    // 取消预约, 空出的名额在同一事务中按排队顺序分配给候补的学生
+    // 时间段开始后不能再取消, 未到场只能登记为缺席
+    pub async fn cancel_reservation_with_db<C>(
+        &self,
+        student_pid: i64,
+        db: &C,
+    ) -> Result<Option<experiment_time_ranges_waitlist::Model>>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        if self.start_time <= Utc::now() {
+            return Err(Error::Conflict(
+                "Time range has already started".to_string(),
+            ));
+        }
+        let txn = db.begin().await?;
+        let deleted =
+            experiment_time_ranges_student_junction::Entity::delete_by_id((self.id, student_pid))
+                .exec(&txn)
+                .await?;
+        if deleted.rows_affected == 0 {
+            return Err(Error::not_found("Reservation"));
+        }
+        Self::release_seat_with_db(self.id, &txn).await?;
+        let promoted =
+            experiment_time_ranges_waitlist::Model::promote_next_with_db(self.id, &txn).await?;
+        txn.commit().await?;
+        Ok(promoted)
+    }
+}
+
+impl ActiveModel {
+    pub fn new(
+        experiment_pid: i64,
+        start_time: DateTimeUtc,
+        end_time: DateTimeUtc,
+        capacity: i32,
+    ) -> Result<Self> {
+        if start_time >= end_time {
+            return Err(Error::BadRequest(
+                "Start time must be earlier than end time".to_string(),
+            ));
+        }
+        if capacity <= 0 {
+            return Err(Error::BadRequest("Capacity must be positive".to_string()));
+        }
+        Ok(Self {
+            id: NotSet,
+            experiment_pid: Set(experiment_pid),
+            start_time: Set(start_time),
+            end_time: Set(end_time),
+            capacity: Set(capacity),
+            reserved: Set(0),
+            series_pid: Set(None),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Experiment,
+    Series,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Experiment => Entity::belongs_to(super::experiment::Entity)
+                .from(Column::ExperimentPid)
+                .to(super::experiment::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+            Relation::Series => Entity::belongs_to(super::time_range_series::Entity)
+                .from(Column::SeriesPid)
+                .to(super::time_range_series::Column::Id)
+                .on_delete(ForeignKeyAction::SetNull)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::experiment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Experiment.def()
+    }
+}
+
+impl Related<super::time_range_series::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Series.def()
+    }
+}
+
+impl Related<super::student::Entity> for Entity {
+    fn to() -> RelationDef {
+        experiment_time_ranges_student_junction::Relation::Student.def()
+    }
+
+    fn via() -> Option<RelationDef> {
+        Some(
+            experiment_time_ranges_student_junction::Relation::TimeRange
+                .def()
+                .rev(),
+        )
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::testing::{
+        test_db, ExperimentBuilder, StudentBuilder, TeacherBuilder, TimeRangeBuilder,
+    };
+
+    #[tokio::test]
+    async fn test_reserve_respects_capacity() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let alice = StudentBuilder::new().insert(&db).await;
+        let bob = StudentBuilder::new().insert(&db).await;
+        let outsider = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new()
+            .student(&alice)
+            .student(&bob)
+            .insert(&db)
+            .await;
+        let time_range = TimeRangeBuilder::new(&experiment)
+            .capacity(1)
+            .insert(&db)
+            .await;
+
+        assert!(matches!(
+            time_range.reserve_with_db(outsider.id, &config, &db).await,
+            Err(Error::Forbidden(_))
+        ));
+        time_range
+            .reserve_with_db(alice.id, &config, &db)
+            .await
+            .unwrap();
+        assert!(matches!(
+            time_range.reserve_with_db(alice.id, &config, &db).await,
+            Err(Error::Conflict(_))
+        ));
+        assert!(matches!(
+            time_range.reserve_with_db(bob.id, &config, &db).await,
+            Err(Error::Conflict(_))
+        ));
+        let time_range = Model::find_by_id_with_db(time_range.id, &db).await.unwrap();
+        assert_eq!(time_range.reserved, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reserve_rejects_archived_experiment() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let teacher = TeacherBuilder::new().insert(&db).await;
+        let alice = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new()
+            .teacher(&teacher)
+            .student(&alice)
+            .insert(&db)
+            .await;
+        assert!(experiment
+            .is_owned_by_with_db(teacher.id, &db)
+            .await
+            .unwrap());
+        let time_range = TimeRangeBuilder::new(&experiment).insert(&db).await;
+
+        experiment.set_archived_with_db(true, &db).await.unwrap();
+        assert!(matches!(
+            time_range.reserve_with_db(alice.id, &config, &db).await,
+            Err(Error::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reserve_rejects_blackout_date() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let alice = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new().student(&alice).insert(&db).await;
+        let time_range = TimeRangeBuilder::new(&experiment).insert(&db).await;
+        let date = time_range
+            .start_time
+            .with_timezone(&config.timezone())
+            .date_naive();
+        blackout_date::ActiveModel::new(date, date, "校庆".to_string())
+            .unwrap()
+            .insert(&db)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            time_range.reserve_with_db(alice.id, &config, &db).await,
+            Err(Error::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_promotes_waitlist() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let alice = StudentBuilder::new().insert(&db).await;
+        let bob = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new()
+            .student(&alice)
+            .student(&bob)
+            .insert(&db)
+            .await;
+        let time_range = TimeRangeBuilder::new(&experiment)
+            .capacity(1)
+            .insert(&db)
+            .await;
+        time_range
+            .reserve_with_db(alice.id, &config, &db)
+            .await
+            .unwrap();
+        let time_range = Model::find_by_id_with_db(time_range.id, &db).await.unwrap();
+        experiment_time_ranges_waitlist::Model::join_with_db(&time_range, bob.id, &db)
+            .await
+            .unwrap();
+
+        let promoted = time_range
+            .cancel_reservation_with_db(alice.id, &db)
+            .await
+            .unwrap()
+            .expect("bob is promoted");
+        assert_eq!(promoted.student_pid, bob.id);
+        let reservation =
+            experiment_time_ranges_student_junction::Entity::find_by_id((time_range.id, bob.id))
+                .one(&db)
+                .await
+                .unwrap();
+        assert!(reservation.is_some());
+        let time_range = Model::find_by_id_with_db(time_range.id, &db).await.unwrap();
+        assert_eq!(time_range.reserved, 1);
+    }
+}
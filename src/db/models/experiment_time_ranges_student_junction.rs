@@ -0,0 +1,390 @@
+// experiment_time_ranges和student的多对多关系 连接表, 每条记录是一次预约
+
+use std::collections::HashSet;
+
+use chrono::Utc;
+use sea_orm::{entity::prelude::*, Condition, QueryOrder, QuerySelect, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+use super::{board, experiment, experiment_time_ranges};
+use crate::error::{Error, Result};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "experiment_time_ranges_student_junction")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub time_range_pid: i64,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub student_pid: i64,
+    // 预约时间
+    pub created_at: DateTimeUtc,
+    // 分配的开发板
+    pub board_pid: Option<i64>,
+}
+
+impl Model {
+    // 为预约分配开发板, 开发板必须可用且在时间重叠的其他预约中未被占用
+    // 先锁定开发板所在的行, 同一块开发板的并发分配按顺序执行
+    pub async fn assign_board_with_db<C>(self, board_pid: i64, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let txn = db.begin().await?;
+        let board = board::Entity::find_by_id(board_pid)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(Error::not_found("Board"))?;
+        if !board.is_available() {
+            return Err(Error::Conflict("Board is not available".to_string()));
+        }
+        let time_range =
+            experiment_time_ranges::Model::find_by_id_with_db(self.time_range_pid, &txn).await?;
+        let experiment =
+            experiment::Model::find_by_id_with_db(time_range.experiment_pid, &txn).await?;
+        if matches!(&experiment.board_model, Some(model) if *model != board.model) {
+            return Err(Error::Conflict(
+                "Board model does not match the experiment".to_string(),
+            ));
+        }
+        let occupied = Entity::find()
+            .inner_join(experiment_time_ranges::Entity)
+            .filter(Column::BoardPid.eq(board_pid))
+            .filter(overlapping(&time_range))
+            .filter(
+                Condition::any()
+                    .add(Column::TimeRangePid.ne(self.time_range_pid))
+                    .add(Column::StudentPid.ne(self.student_pid)),
+            )
+            .count(&txn)
+            .await?;
+        if occupied > 0 {
+            return Err(Error::Conflict(
+                "Board is already assigned in an overlapping time range".to_string(),
+            ));
+        }
+
+        let mut reservation: ActiveModel = self.into();
+        reservation.board_pid = Set(Some(board_pid));
+        let reservation = reservation.update(&txn).await?;
+        txn.commit().await?;
+        Ok(reservation)
+    }
+
+    pub async fn unassign_board_with_db<C>(self, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let mut reservation: ActiveModel = self.into();
+        reservation.board_pid = Set(None);
+        Ok(reservation.update(db).await?)
+    }
+
+    // 给时间段内所有未分配开发板的预约自动分配空闲的开发板, 开发板不足时剩余预约保持未分配
+    // 实验指定了开发板型号时只分配该型号的开发板
+    pub async fn assign_free_boards_with_db<C>(
+        time_range: &experiment_time_ranges::Model,
+        db: &C,
+    ) -> Result<Vec<Self>>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let txn = db.begin().await?;
+        let experiment =
+            experiment::Model::find_by_id_with_db(time_range.experiment_pid, &txn).await?;
+        let mut candidates =
+            board::Entity::find().filter(board::Column::Status.eq(board::BoardStatus::Available));
+        if let Some(model) = experiment.board_model {
+            candidates = candidates.filter(board::Column::Model.eq(model));
+        }
+        // 锁定候选开发板后再统计占用情况
+        let candidates = candidates
+            .order_by_asc(board::Column::Id)
+            .lock_exclusive()
+            .all(&txn)
+            .await?;
+        let occupied: HashSet<i64> = Entity::find()
+            .inner_join(experiment_time_ranges::Entity)
+            .filter(Column::BoardPid.is_not_null())
+            .filter(overlapping(time_range))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .filter_map(|reservation| reservation.board_pid)
+            .collect();
+        let free_boards = candidates
+            .into_iter()
+            .filter(|board| !occupied.contains(&board.id));
+        let unassigned = Entity::find()
+            .filter(Column::TimeRangePid.eq(time_range.id))
+            .filter(Column::BoardPid.is_null())
+            .order_by_asc(Column::CreatedAt)
+            .all(&txn)
+            .await?;
+
+        let mut assigned = Vec::new();
+        for (reservation, board) in unassigned.into_iter().zip(free_boards) {
+            let mut reservation: ActiveModel = reservation.into();
+            reservation.board_pid = Set(Some(board.id));
+            assigned.push(reservation.update(&txn).await?);
+        }
+        txn.commit().await?;
+        Ok(assigned)
+    }
+}
+
+// 与给定时间段重叠的时间段
+fn overlapping(time_range: &experiment_time_ranges::Model) -> Condition {
+    Condition::all()
+        .add(experiment_time_ranges::Column::StartTime.lt(time_range.end_time))
+        .add(experiment_time_ranges::Column::EndTime.gt(time_range.start_time))
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    TimeRange,
+    Student,
+    Board,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::TimeRange => Entity::belongs_to(super::experiment_time_ranges::Entity)
+                .from(Column::TimeRangePid)
+                .to(super::experiment_time_ranges::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+            Relation::Student => Entity::belongs_to(super::student::Entity)
+                .from(Column::StudentPid)
+                .to(super::student::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+            Relation::Board => Entity::belongs_to(super::board::Entity)
+                .from(Column::BoardPid)
+                .to(super::board::Column::Id)
+                .on_delete(ForeignKeyAction::SetNull)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::student::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Student.def()
+    }
+}
+
+impl Related<super::experiment_time_ranges::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TimeRange.def()
+    }
+}
+
+impl Related<super::board::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Board.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl ActiveModel {
+    pub fn new(time_range_pid: i64, student_pid: i64) -> Self {
+        Self {
+            time_range_pid: Set(time_range_pid),
+            student_pid: Set(student_pid),
+            created_at: Set(Utc::now()),
+            board_pid: Set(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::{
+        config::ReservationConfig,
+        db::{
+            models::experiment::ExperimentFields,
+            testing::{
+                shared_test_db, test_db, ExperimentBuilder, StudentBuilder, TimeRangeBuilder,
+            },
+        },
+    };
+
+    async fn insert_board(model: &str, serial: &str, db: &DatabaseConnection) -> board::Model {
+        board::ActiveModel::new(model.to_string(), serial.to_string(), None)
+            .insert(db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_assign_and_unassign_board() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let alice = StudentBuilder::new().insert(&db).await;
+        let bob = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new()
+            .student(&alice)
+            .student(&bob)
+            .insert(&db)
+            .await;
+        let start_time = Utc::now() + Duration::days(1);
+        let first = TimeRangeBuilder::new(&experiment)
+            .start_time(start_time)
+            .insert(&db)
+            .await;
+        // 与第一个时间段重叠一小时
+        let overlapping = TimeRangeBuilder::new(&experiment)
+            .start_time(start_time + Duration::hours(1))
+            .insert(&db)
+            .await;
+        let later = TimeRangeBuilder::new(&experiment)
+            .start_time(start_time + Duration::days(2))
+            .insert(&db)
+            .await;
+        let board = insert_board("EGO1", "E-001", &db).await;
+        let alice_reservation = first.reserve_with_db(alice.id, &config, &db).await.unwrap();
+        let bob_reservation = overlapping
+            .reserve_with_db(bob.id, &config, &db)
+            .await
+            .unwrap();
+        let later_reservation = later.reserve_with_db(bob.id, &config, &db).await.unwrap();
+
+        let alice_reservation = alice_reservation
+            .assign_board_with_db(board.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(alice_reservation.board_pid, Some(board.id));
+        // 重复分配给同一个预约不算冲突
+        let alice_reservation = alice_reservation
+            .assign_board_with_db(board.id, &db)
+            .await
+            .unwrap();
+        assert!(matches!(
+            bob_reservation
+                .clone()
+                .assign_board_with_db(board.id, &db)
+                .await,
+            Err(Error::Conflict(_))
+        ));
+        // 不重叠的时间段可以使用同一块开发板
+        later_reservation
+            .assign_board_with_db(board.id, &db)
+            .await
+            .unwrap();
+
+        let alice_reservation = alice_reservation.unassign_board_with_db(&db).await.unwrap();
+        assert_eq!(alice_reservation.board_pid, None);
+        let bob_reservation = bob_reservation
+            .assign_board_with_db(board.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(bob_reservation.board_pid, Some(board.id));
+    }
+
+    #[tokio::test]
+    async fn test_free_boards_match_experiment_model() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let alice = StudentBuilder::new().insert(&db).await;
+        let bob = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new()
+            .student(&alice)
+            .student(&bob)
+            .insert(&db)
+            .await;
+        let title = experiment.title.clone();
+        experiment
+            .clone()
+            .update_with_db(
+                ExperimentFields {
+                    title,
+                    board_model: Some("EGO1".to_string()),
+                    ..Default::default()
+                },
+                &db,
+            )
+            .await
+            .unwrap();
+        let time_range = TimeRangeBuilder::new(&experiment).insert(&db).await;
+        let basys = insert_board("Basys3", "B-001", &db).await;
+        let ego = insert_board("EGO1", "E-001", &db).await;
+        let alice_reservation = time_range
+            .reserve_with_db(alice.id, &config, &db)
+            .await
+            .unwrap();
+        time_range
+            .reserve_with_db(bob.id, &config, &db)
+            .await
+            .unwrap();
+
+        // 只有一块EGO1, 另一个预约保持未分配
+        let assigned = Model::assign_free_boards_with_db(&time_range, &db)
+            .await
+            .unwrap();
+        assert_eq!(assigned.len(), 1);
+        assert_eq!(assigned[0].board_pid, Some(ego.id));
+        assert!(assigned
+            .iter()
+            .all(|reservation| reservation.board_pid != Some(basys.id)));
+
+        // 手动分配时也检查型号
+        assert!(matches!(
+            alice_reservation.assign_board_with_db(basys.id, &db).await,
+            Err(Error::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_assignments_of_one_board() {
+        let shared = shared_test_db(4).await;
+        let db = &shared.db;
+        let config = ReservationConfig::default();
+        let alice = StudentBuilder::new().insert(db).await;
+        let bob = StudentBuilder::new().insert(db).await;
+        let experiment = ExperimentBuilder::new()
+            .student(&alice)
+            .student(&bob)
+            .insert(db)
+            .await;
+        let start_time = Utc::now() + Duration::days(1);
+        let first = TimeRangeBuilder::new(&experiment)
+            .start_time(start_time)
+            .insert(db)
+            .await;
+        let overlapping = TimeRangeBuilder::new(&experiment)
+            .start_time(start_time + Duration::hours(1))
+            .insert(db)
+            .await;
+        let board = insert_board("EGO1", "E-001", db).await;
+        let alice_reservation = first.reserve_with_db(alice.id, &config, db).await.unwrap();
+        let bob_reservation = overlapping
+            .reserve_with_db(bob.id, &config, db)
+            .await
+            .unwrap();
+
+        // 两个连接同时分配同一块开发板, 只有一个能成功
+        let (alice_result, bob_result) = tokio::join!(
+            alice_reservation.assign_board_with_db(board.id, db),
+            bob_reservation.assign_board_with_db(board.id, db),
+        );
+        assert_eq!(
+            [alice_result.is_ok(), bob_result.is_ok()]
+                .iter()
+                .filter(|ok| **ok)
+                .count(),
+            1
+        );
+        let holders = Entity::find()
+            .filter(Column::BoardPid.eq(board.id))
+            .count(db)
+            .await
+            .unwrap();
+        assert_eq!(holders, 1);
+    }
+}
@@ -0,0 +1,487 @@
+// 已满时间段的候补队列, 按id先后顺序递补
+
+use chrono::Utc;
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, QueryOrder, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+use super::{experiment, experiment_time_ranges, experiment_time_ranges_student_junction};
+use crate::error::{Error, Result};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "experiment_time_ranges_waitlist")]
+pub struct Model {
+    // 自增主键, 同时决定排队顺序
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 时间段主键
+    pub time_range_pid: i64,
+    // 学生主键
+    pub student_pid: i64,
+    // 加入候补的时间
+    pub created_at: DateTimeUtc,
+    // 递补成功的时间, 为空表示仍在排队
+    pub promoted_at: Option<DateTimeUtc>,
+    // 学生确认已收到递补通知的时间
+    pub acknowledged_at: Option<DateTimeUtc>,
+}
+
+impl Model {
+    // 加入候补, 只有已满的时间段才能候补
+    pub async fn join_with_db<C>(
+        time_range: &experiment_time_ranges::Model,
+        student_pid: i64,
+        db: &C,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        if time_range.start_time <= Utc::now() {
+            return Err(Error::BadRequest(
+                "Time range has already started".to_string(),
+            ));
+        }
+        time_range
+            .ensure_student_eligible_with_db(student_pid, db)
+            .await?;
+        if time_range.remaining() > 0 {
+            return Err(Error::BadRequest(
+                "Time range is not full, reserve it directly".to_string(),
+            ));
+        }
+        let reserved = experiment_time_ranges_student_junction::Entity::find_by_id((
+            time_range.id,
+            student_pid,
+        ))
+        .one(db)
+        .await?;
+        if reserved.is_some() {
+            return Err(Error::Conflict(
+                "Student has already reserved the time range".to_string(),
+            ));
+        }
+        if Self::find_waiting_with_db(time_range.id, student_pid, db)
+            .await?
+            .is_some()
+        {
+            return Err(Error::Conflict(
+                "Student is already on the waitlist".to_string(),
+            ));
+        }
+        Ok(ActiveModel::new(time_range.id, student_pid)
+            .insert(db)
+            .await?)
+    }
+
+    pub async fn leave_with_db<C>(time_range_pid: i64, student_pid: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let entry = Self::find_waiting_with_db(time_range_pid, student_pid, db)
+            .await?
+            .ok_or(Error::not_found("Waitlist entry"))?;
+        entry.delete(db).await?;
+        Ok(())
+    }
+
+    pub async fn find_waiting_with_db<C>(
+        time_range_pid: i64,
+        student_pid: i64,
+        db: &C,
+    ) -> Result<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .filter(Column::TimeRangePid.eq(time_range_pid))
+            .filter(Column::StudentPid.eq(student_pid))
+            .filter(Column::PromotedAt.is_null())
+            .one(db)
+            .await?)
+    }
+
+    // 排队位置, 从1开始
+    pub async fn position_with_db<C>(&self, db: &C) -> Result<u64>
+    where
+        C: ConnectionTrait,
+    {
+        let ahead = Entity::find()
+            .filter(Column::TimeRangePid.eq(self.time_range_pid))
+            .filter(Column::PromotedAt.is_null())
+            .filter(Column::Id.lt(self.id))
+            .count(db)
+            .await?;
+        Ok(ahead + 1)
+    }
+
+    // 把空出的名额分配给排在最前面的学生, 需要在释放名额的同一事务中调用
+    // 已经开始的时间段和不再开放预约的实验不再递补
+    pub(crate) async fn promote_next_with_db<C>(time_range_pid: i64, db: &C) -> Result<Option<Self>>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let time_range =
+            experiment_time_ranges::Model::find_by_id_with_db(time_range_pid, db).await?;
+        if time_range.start_time <= Utc::now()
+            || !experiment::Model::find_by_id_with_db(time_range.experiment_pid, db)
+                .await?
+                .is_open()
+        {
+            return Ok(None);
+        }
+        let waiting = Entity::find()
+            .filter(Column::TimeRangePid.eq(time_range_pid))
+            .filter(Column::PromotedAt.is_null())
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?;
+        for entry in waiting {
+            // 已退选或被暂停预约的学生不能递补, 移出候补队列
+            match time_range
+                .ensure_student_eligible_with_db(entry.student_pid, db)
+                .await
+            {
+                Ok(()) => {}
+                Err(Error::Forbidden(reason)) => {
+                    tracing::info!("Dropping waitlist entry {}: {}", entry.id, reason);
+                    entry.delete(db).await?;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+            if !experiment_time_ranges::Model::occupy_seat_with_db(time_range_pid, db).await? {
+                return Ok(None);
+            }
+            // 用保存点隔离单个学生的失败(例如已通过其他途径预约), 不影响后面的学生
+            let savepoint = db.begin().await?;
+            match experiment_time_ranges_student_junction::ActiveModel::new(
+                time_range_pid,
+                entry.student_pid,
+            )
+            .insert(&savepoint)
+            .await
+            {
+                Ok(_) => {
+                    savepoint.commit().await?;
+                    let mut entry: ActiveModel = entry.into();
+                    entry.promoted_at = Set(Some(Utc::now()));
+                    return Ok(Some(entry.update(db).await?));
+                }
+                Err(e) => {
+                    savepoint.rollback().await?;
+                    tracing::warn!("Failed to promote waitlist entry {}: {:?}", entry.id, e);
+                    // 归还占用的名额, 并移除无法递补的排队记录
+                    experiment_time_ranges::Model::release_seat_with_db(time_range_pid, db).await?;
+                    entry.delete(db).await?;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // 学生尚未确认的递补通知
+    pub async fn find_unacknowledged_with_db<C>(student_pid: i64, db: &C) -> Result<Vec<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .filter(Column::StudentPid.eq(student_pid))
+            .filter(Column::PromotedAt.is_not_null())
+            .filter(Column::AcknowledgedAt.is_null())
+            .order_by_asc(Column::PromotedAt)
+            .all(db)
+            .await?)
+    }
+
+    // 学生本人的候补记录
+    pub async fn find_own_with_db<C>(id: i64, student_pid: i64, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::find_by_id(id)
+            .filter(Column::StudentPid.eq(student_pid))
+            .one(db)
+            .await?
+            .ok_or(Error::not_found("Waitlist entry"))
+    }
+
+    pub async fn acknowledge_with_db<C>(self, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        if self.promoted_at.is_none() {
+            return Err(Error::BadRequest(
+                "Waitlist entry has not been promoted".to_string(),
+            ));
+        }
+        let mut entry: ActiveModel = self.into();
+        entry.acknowledged_at = Set(Some(Utc::now()));
+        Ok(entry.update(db).await?)
+    }
+}
+
+impl ActiveModel {
+    pub fn new(time_range_pid: i64, student_pid: i64) -> Self {
+        Self {
+            id: NotSet,
+            time_range_pid: Set(time_range_pid),
+            student_pid: Set(student_pid),
+            created_at: Set(Utc::now()),
+            promoted_at: Set(None),
+            acknowledged_at: Set(None),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    TimeRange,
+    Student,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::TimeRange => Entity::belongs_to(super::experiment_time_ranges::Entity)
+                .from(Column::TimeRangePid)
+                .to(super::experiment_time_ranges::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+            Relation::Student => Entity::belongs_to(super::student::Entity)
+                .from(Column::StudentPid)
+                .to(super::student::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::experiment_time_ranges::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TimeRange.def()
+    }
+}
+
+impl Related<super::student::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Student.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::{
+        config::ReservationConfig,
+        db::{
+            models::{experiment_student_junction, student_suspension},
+            testing::{test_db, ExperimentBuilder, StudentBuilder, TimeRangeBuilder},
+        },
+    };
+
+    #[tokio::test]
+    async fn test_join_position_and_leave() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let alice = StudentBuilder::new().insert(&db).await;
+        let bob = StudentBuilder::new().insert(&db).await;
+        let carol = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new()
+            .student(&alice)
+            .student(&bob)
+            .student(&carol)
+            .insert(&db)
+            .await;
+        let time_range = TimeRangeBuilder::new(&experiment)
+            .capacity(1)
+            .insert(&db)
+            .await;
+
+        // 未满时不能候补
+        assert!(matches!(
+            Model::join_with_db(&time_range, bob.id, &db).await,
+            Err(Error::BadRequest(_))
+        ));
+        time_range
+            .reserve_with_db(alice.id, &config, &db)
+            .await
+            .unwrap();
+        let time_range = experiment_time_ranges::Model::find_by_id_with_db(time_range.id, &db)
+            .await
+            .unwrap();
+        assert!(matches!(
+            Model::join_with_db(&time_range, alice.id, &db).await,
+            Err(Error::Conflict(_))
+        ));
+
+        let bob_entry = Model::join_with_db(&time_range, bob.id, &db).await.unwrap();
+        let carol_entry = Model::join_with_db(&time_range, carol.id, &db)
+            .await
+            .unwrap();
+        assert!(matches!(
+            Model::join_with_db(&time_range, bob.id, &db).await,
+            Err(Error::Conflict(_))
+        ));
+        assert_eq!(bob_entry.position_with_db(&db).await.unwrap(), 1);
+        assert_eq!(carol_entry.position_with_db(&db).await.unwrap(), 2);
+
+        Model::leave_with_db(time_range.id, bob.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(carol_entry.position_with_db(&db).await.unwrap(), 1);
+        assert!(matches!(
+            Model::leave_with_db(time_range.id, bob.id, &db).await,
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_promotion_and_acknowledge() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let alice = StudentBuilder::new().insert(&db).await;
+        let bob = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new()
+            .student(&alice)
+            .student(&bob)
+            .insert(&db)
+            .await;
+        let time_range = TimeRangeBuilder::new(&experiment)
+            .capacity(1)
+            .insert(&db)
+            .await;
+        time_range
+            .reserve_with_db(alice.id, &config, &db)
+            .await
+            .unwrap();
+        let time_range = experiment_time_ranges::Model::find_by_id_with_db(time_range.id, &db)
+            .await
+            .unwrap();
+        let entry = Model::join_with_db(&time_range, bob.id, &db).await.unwrap();
+
+        // 尚未递补的记录不能确认
+        assert!(matches!(
+            entry.clone().acknowledge_with_db(&db).await,
+            Err(Error::BadRequest(_))
+        ));
+
+        let promoted = time_range
+            .cancel_reservation_with_db(alice.id, &db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(promoted.id, entry.id);
+        assert!(promoted.promoted_at.is_some());
+        assert!(
+            experiment_time_ranges_student_junction::Entity::find_by_id((time_range.id, bob.id))
+                .one(&db)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(Model::find_waiting_with_db(time_range.id, bob.id, &db)
+            .await
+            .unwrap()
+            .is_none());
+
+        assert_eq!(
+            Model::find_unacknowledged_with_db(bob.id, &db)
+                .await
+                .unwrap(),
+            vec![promoted.clone()]
+        );
+        // 只能确认自己的递补通知
+        assert!(matches!(
+            Model::find_own_with_db(promoted.id, alice.id, &db).await,
+            Err(Error::NotFound(_))
+        ));
+        let acknowledged = Model::find_own_with_db(promoted.id, bob.id, &db)
+            .await
+            .unwrap()
+            .acknowledge_with_db(&db)
+            .await
+            .unwrap();
+        assert!(acknowledged.acknowledged_at.is_some());
+        assert!(Model::find_unacknowledged_with_db(bob.id, &db)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_promotion_skips_ineligible_students() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let students = [
+            StudentBuilder::new().insert(&db).await,
+            StudentBuilder::new().insert(&db).await,
+            StudentBuilder::new().insert(&db).await,
+            StudentBuilder::new().insert(&db).await,
+            StudentBuilder::new().insert(&db).await,
+        ];
+        let [alice, bob, carol, dave, erin] = &students;
+        let experiment = students
+            .iter()
+            .fold(ExperimentBuilder::new(), |builder, student| {
+                builder.student(student)
+            })
+            .insert(&db)
+            .await;
+        let time_range = TimeRangeBuilder::new(&experiment)
+            .capacity(1)
+            .insert(&db)
+            .await;
+        time_range
+            .reserve_with_db(alice.id, &config, &db)
+            .await
+            .unwrap();
+        let time_range = experiment_time_ranges::Model::find_by_id_with_db(time_range.id, &db)
+            .await
+            .unwrap();
+        for student in [bob, carol, dave] {
+            Model::join_with_db(&time_range, student.id, &db)
+                .await
+                .unwrap();
+        }
+
+        // bob退选, carol被暂停预约, 名额递补给dave
+        experiment_student_junction::Entity::delete_by_id((experiment.id, bob.id))
+            .exec(&db)
+            .await
+            .unwrap();
+        student_suspension::ActiveModel::new(carol.id, Utc::now() + Duration::days(7))
+            .insert(&db)
+            .await
+            .unwrap();
+        let promoted = time_range
+            .cancel_reservation_with_db(alice.id, &db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(promoted.student_pid, dave.id);
+        for student in [bob, carol] {
+            assert!(Model::find_waiting_with_db(time_range.id, student.id, &db)
+                .await
+                .unwrap()
+                .is_none());
+        }
+
+        // 实验归档后不再递补
+        Model::join_with_db(&time_range, erin.id, &db)
+            .await
+            .unwrap();
+        experiment.set_archived_with_db(true, &db).await.unwrap();
+        assert!(time_range
+            .cancel_reservation_with_db(dave.id, &db)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(Model::find_waiting_with_db(time_range.id, erin.id, &db)
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,299 @@
+// 登录失败记录, 按账号和IP分别计数, 保存在数据库中以便重启后仍然生效
+
+use chrono::{Duration, Utc};
+use sea_orm::{
+    entity::prelude::*,
+    sea_query::{Expr, OnConflict},
+    ActiveValue::NotSet,
+    Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::LoginConfig,
+    error::{Error, Result},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleScope {
+    // 学生登录名(学号或账号)
+    #[sea_orm(string_value = "student")]
+    Student,
+    // 教师登录名(工号或账号)
+    #[sea_orm(string_value = "teacher")]
+    Teacher,
+    // 客户端IP
+    #[sea_orm(string_value = "ip")]
+    Ip,
+}
+
+impl ThrottleScope {
+    // 返回(允许的连续失败次数, 锁定阈值)
+    fn limits(&self, config: &LoginConfig) -> (i32, i32) {
+        match self {
+            ThrottleScope::Student | ThrottleScope::Teacher => (
+                config.account_free_attempts,
+                config.account_lockout_threshold,
+            ),
+            ThrottleScope::Ip => (config.ip_free_attempts, config.ip_lockout_threshold),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "login_throttle")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 计数对象的类型
+    pub scope: ThrottleScope,
+    // 登录名或IP
+    pub key: String,
+    // 连续失败次数
+    pub failures: i32,
+    // 最近一次失败的时间
+    pub last_failure_at: DateTimeUtc,
+    // 在此时间之前拒绝登录
+    pub blocked_until: Option<DateTimeUtc>,
+}
+
+// 根据连续失败次数计算需要等待的时间, 不需要等待时返回None
+pub fn block_duration(
+    failures: i32,
+    scope: ThrottleScope,
+    config: &LoginConfig,
+) -> Option<Duration> {
+    let (free_attempts, lockout_threshold) = scope.limits(config);
+    if failures >= lockout_threshold {
+        return Some(Duration::seconds(config.lockout_secs));
+    }
+    if failures <= free_attempts {
+        return None;
+    }
+    let exponent = (failures - free_attempts - 1).min(30) as u32;
+    let secs = config
+        .backoff_base_secs
+        .saturating_mul(1i64 << exponent)
+        .min(config.max_backoff_secs);
+    Some(Duration::seconds(secs))
+}
+
+impl Model {
+    async fn find_with_db<C>(scope: ThrottleScope, key: &str, db: &C) -> Result<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .filter(Column::Scope.eq(scope))
+            .filter(Column::Key.eq(key))
+            .one(db)
+            .await?)
+    }
+
+    // 仍在等待或锁定期间时拒绝登录
+    pub async fn check_with_db<C>(scope: ThrottleScope, key: &str, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let now = Utc::now();
+        match Self::find_with_db(scope, key, db).await? {
+            Some(Model {
+                blocked_until: Some(blocked_until),
+                ..
+            }) if blocked_until > now => Err(Error::TooManyAttempts {
+                retry_after_secs: (blocked_until - now).num_seconds().max(1),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // 记录一次失败并计算下一次允许登录的时间
+    // 计数用数据库端的自增完成, 并发的失败请求不会互相覆盖
+    pub async fn record_failure_with_db<C>(
+        scope: ThrottleScope,
+        key: &str,
+        config: &LoginConfig,
+        db: &C,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let now = Utc::now();
+        let txn = db.begin().await?;
+        Entity::insert(ActiveModel {
+            id: NotSet,
+            scope: Set(scope),
+            key: Set(key.to_string()),
+            failures: Set(0),
+            last_failure_at: Set(now),
+            blocked_until: Set(None),
+        })
+        .on_conflict(
+            OnConflict::columns([Column::Scope, Column::Key])
+                .do_nothing()
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec(&txn)
+        .await?;
+        // 距上次失败太久则重新计数
+        Entity::update_many()
+            .col_expr(Column::Failures, Expr::value(0))
+            .filter(Column::Scope.eq(scope))
+            .filter(Column::Key.eq(key))
+            .filter(Column::LastFailureAt.lt(now - Duration::seconds(config.failure_window_secs)))
+            .exec(&txn)
+            .await?;
+        Entity::update_many()
+            .col_expr(Column::Failures, Expr::col(Column::Failures).add(1))
+            .col_expr(Column::LastFailureAt, Expr::value(now))
+            .filter(Column::Scope.eq(scope))
+            .filter(Column::Key.eq(key))
+            .exec(&txn)
+            .await?;
+
+        let mut model = Self::find_with_db(scope, key, &txn)
+            .await?
+            .ok_or(Error::internal("Login throttle record disappeared"))?;
+        model.blocked_until =
+            block_duration(model.failures, scope, config).map(|duration| now + duration);
+        let model = ActiveModel {
+            blocked_until: Set(model.blocked_until),
+            ..model.into()
+        }
+        .update(&txn)
+        .await?;
+        txn.commit().await?;
+        if model.failures == scope.limits(config).1 {
+            tracing::warn!("Login locked for {:?} {}", scope, key);
+        }
+        Ok(model)
+    }
+
+    // 登录成功或管理员解锁时清除记录
+    pub async fn clear_with_db<C>(scope: ThrottleScope, key: &str, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::delete_many()
+            .filter(Column::Scope.eq(scope))
+            .filter(Column::Key.eq(key))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    // 当前被限制登录的记录
+    pub async fn find_blocked_with_db<C>(db: &C) -> Result<Vec<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .filter(Column::BlockedUntil.gt(Utc::now()))
+            .all(db)
+            .await?)
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match *self {}
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::testing::{shared_test_db, test_db};
+
+    #[test]
+    fn test_block_duration() {
+        let config = LoginConfig::default();
+        let scope = ThrottleScope::Student;
+        assert_eq!(block_duration(3, scope, &config), None);
+        assert_eq!(
+            block_duration(4, scope, &config),
+            Some(Duration::seconds(1))
+        );
+        assert_eq!(
+            block_duration(6, scope, &config),
+            Some(Duration::seconds(4))
+        );
+        assert_eq!(
+            block_duration(10, scope, &config),
+            Some(Duration::seconds(config.lockout_secs))
+        );
+        assert_eq!(block_duration(10, ThrottleScope::Ip, &config), None);
+    }
+
+    #[tokio::test]
+    async fn test_lockout_and_clear() {
+        let db = test_db().await;
+        let config = LoginConfig {
+            account_free_attempts: 1,
+            account_lockout_threshold: 2,
+            ..Default::default()
+        };
+        let scope = ThrottleScope::Student;
+
+        Model::record_failure_with_db(scope, "2021001", &config, &db)
+            .await
+            .unwrap();
+        Model::check_with_db(scope, "2021001", &db).await.unwrap();
+        let locked = Model::record_failure_with_db(scope, "2021001", &config, &db)
+            .await
+            .unwrap();
+        assert_eq!(locked.failures, 2);
+        assert!(matches!(
+            Model::check_with_db(scope, "2021001", &db).await,
+            Err(Error::TooManyAttempts { .. })
+        ));
+        // 其他账号不受影响
+        Model::check_with_db(scope, "2021002", &db).await.unwrap();
+        assert_eq!(Model::find_blocked_with_db(&db).await.unwrap().len(), 1);
+
+        Model::clear_with_db(scope, "2021001", &db).await.unwrap();
+        Model::check_with_db(scope, "2021001", &db).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_failures_and_window() {
+        // 使用多个连接, 失败记录才会真正并发写入
+        let shared = shared_test_db(5).await;
+        let db = &shared.db;
+        let config = LoginConfig::default();
+        let scope = ThrottleScope::Ip;
+
+        let results = futures::future::join_all(
+            (0..5).map(|_| Model::record_failure_with_db(scope, "10.0.0.1", &config, db)),
+        )
+        .await;
+        assert!(results.iter().all(|result| result.is_ok()));
+        let model = Model::find_with_db(scope, "10.0.0.1", db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(model.failures, 5);
+
+        // 超出计数窗口后重新计数
+        ActiveModel {
+            last_failure_at: Set(Utc::now() - Duration::seconds(config.failure_window_secs + 1)),
+            ..model.into()
+        }
+        .update(db)
+        .await
+        .unwrap();
+        let model = Model::record_failure_with_db(scope, "10.0.0.1", &config, db)
+            .await
+            .unwrap();
+        assert_eq!(model.failures, 1);
+    }
+}
@@ -1,12 +1,22 @@
+pub mod blackout_date;
+pub mod board;
+pub mod calendar_feed;
 pub mod class;
 pub mod class_student_junction;
 pub mod class_teacher_junction;
 pub mod experiment;
+pub mod experiment_class_junction;
+pub mod experiment_student_junction;
 pub mod experiment_teacher_junction;
 pub mod experiment_time_ranges;
 pub mod experiment_time_ranges_student_junction;
+pub mod experiment_time_ranges_waitlist;
+pub mod login_throttle;
 pub mod student;
 pub mod student_refresh_token;
+pub mod student_strike;
+pub mod student_suspension;
 pub mod teacher;
 pub mod teacher_refresh_token;
-pub mod experiment_student_junction;
\ No newline at end of file
+pub mod term;
+pub mod time_range_series;
@@ -1,6 +1,9 @@
-use crate::db::{db_conn::get_db_str_result, hash_password, ARGON2};
-use argon2::password_hash::{PasswordHash, PasswordVerifier};
-use sea_orm::{entity::prelude::*, ActiveValue::NotSet, Condition, Set};
+use crate::{
+    db::{argon2, hash_password, password_needs_rehash, validate_new_password},
+    error::{Error, Result},
+};
+use argon2::password_hash::{self, PasswordHash, PasswordVerifier};
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, Condition, Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
 
 #[derive(Default, Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
@@ -17,10 +20,12 @@ pub struct Model {
     pub password_hash: String,
     // 姓名
     pub name: Option<String>,
+    // 需要修改密码后才能使用其他接口, 导入或被重置密码的账号会设置该标记
+    pub must_change_password: bool,
 }
 
 impl Model {
-    pub async fn into_active_model_encrypted(self) -> Result<ActiveModel, String> {
+    pub async fn into_active_model_encrypted(self) -> Result<ActiveModel> {
         let password_hash = hash_password(self.password_hash).await?;
         Ok(ActiveModel {
             id: NotSet,
@@ -28,74 +33,132 @@ impl Model {
             account: Set(self.account),
             password_hash: Set(password_hash),
             name: Set(self.name),
+            must_change_password: Set(self.must_change_password),
         })
     }
 
-    pub async fn join_class_with_db<C>(&self, class_pid: i64, db: &C) -> Result<(), String>
+    pub async fn join_class_with_db<C>(&self, class_pid: i64, db: &C) -> Result<()>
     where
         C: ConnectionTrait,
     {
-        let junction = super::class_student_junction::ActiveModel::new(class_pid, self.id.clone());
-        junction
-            .insert(db)
-            .await
-            .map_err(|e| format!("Failed to join class with db: {:?}", e))?;
-        Ok(())
-    }
-
-    pub async fn join_class(&self, class_pid: i64) -> Result<(), String> {
-        let junction = super::class_student_junction::ActiveModel::new(class_pid, self.id.clone());
-        let db = get_db_str_result().await?;
-        junction
-            .insert(&db)
-            .await
-            .map_err(|e| format!("Failed to join class: {:?}", e))?;
+        let junction = super::class_student_junction::ActiveModel::new(class_pid, self.id);
+        junction.insert(db).await?;
+        // 自动选修布置给该班级的实验
+        let assigned = super::experiment_class_junction::Entity::find()
+            .filter(super::experiment_class_junction::Column::ClassPid.eq(class_pid))
+            .all(db)
+            .await?;
+        for assignment in assigned {
+            super::experiment_student_junction::Model::enroll_many_with_db(
+                assignment.experiment_pid,
+                [self.id],
+                db,
+            )
+            .await?;
+        }
         Ok(())
     }
 
-    pub async fn leave_class(&self, class_pid: i64) -> Result<(), String> {
-        let db = get_db_str_result().await?;
-        let junction =
-            super::class_student_junction::Entity::find_by_id((class_pid, self.id.clone()))
-                .one(&db)
-                .await
-                .map_err(|e| format!("Failed to find junction: {:?}", e))?;
-
-        if let Some(junction) = junction {
-            junction
-                .delete(&db)
-                .await
-                .map_err(|e| format!("Failed to delete junction: {:?}", e))?;
-            Ok(())
-        } else {
-            return Err("Student is not in the class".to_string());
+    // 离开班级不会退选已选修的实验, 保留历史预约记录
+    pub async fn leave_class_with_db<C>(&self, class_pid: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let deleted = super::class_student_junction::Entity::delete_by_id((class_pid, self.id))
+            .exec(db)
+            .await?;
+        if deleted.rows_affected == 0 {
+            return Err(Error::not_found("Class membership"));
         }
+        Ok(())
     }
 
-    pub async fn find_by_student_id_or_account(
+    pub async fn find_by_student_id_or_account_with_db<C>(
         student_id_or_account: String,
-    ) -> Result<Self, String> {
-        let db = get_db_str_result().await?;
-
+        db: &C,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
         Entity::find()
             .filter(
                 Condition::any()
                     .add(Column::StudentId.eq(student_id_or_account.clone()))
                     .add(Column::Account.eq(student_id_or_account)),
             )
-            .one(&db)
-            .await
-            .map_err(|e| format!("{:?}", e))?
-            .ok_or("Student not found".to_string())
+            .one(db)
+            .await?
+            .ok_or(Error::not_found("Student"))
     }
 
-    pub fn verify_password(&self, password: String) -> Result<(), String> {
-        let password_hash = PasswordHash::new(&self.password_hash)
-            .map_err(|e| format!("Failed to create PasswordHash: {:?}", e))?;
+    pub fn verify_password(&self, password: String) -> Result<()> {
+        let password_hash = PasswordHash::new(&self.password_hash).map_err(Error::internal)?;
 
-        ARGON2
+        argon2()
             .verify_password(password.as_bytes(), &password_hash)
-            .map_err(|e| format!("Invalid password: {:?}", e.to_string()))
+            .map_err(|e| match e {
+                password_hash::Error::Password => Error::InvalidCredentials,
+                e => Error::internal(e),
+            })
+    }
+
+    pub fn needs_rehash(&self) -> bool {
+        password_needs_rehash(&self.password_hash)
+    }
+
+    // 用新密码(或以当前参数重新哈希的原密码)更新密码哈希
+    pub async fn set_password_with_db<C>(self, password: String, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let password_hash = hash_password(password).await?;
+        let mut active: ActiveModel = self.into();
+        active.password_hash = Set(password_hash);
+        Ok(active.update(db).await?)
+    }
+
+    // 本人修改密码, 需要提供原密码, 修改后清除强制修改标记
+    pub async fn change_password_with_db<C>(
+        self,
+        old_password: String,
+        new_password: String,
+        db: &C,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        validate_new_password(&new_password)?;
+        if old_password == new_password {
+            return Err(Error::BadRequest(
+                "New password must differ from the old one".to_string(),
+            ));
+        }
+        let model =
+            tokio::task::spawn_blocking(move || self.verify_password(old_password).map(|_| self))
+                .await
+                .map_err(Error::internal)??;
+        let password_hash = hash_password(new_password).await?;
+        let mut active: ActiveModel = model.into();
+        active.password_hash = Set(password_hash);
+        active.must_change_password = Set(false);
+        Ok(active.update(db).await?)
+    }
+
+    // 由管理员重置密码, 用户下次登录后必须修改密码, 已签发的刷新令牌全部失效
+    pub async fn reset_password_with_db<C>(self, new_password: String, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        validate_new_password(&new_password)?;
+        let password_hash = hash_password(new_password).await?;
+        let txn = db.begin().await?;
+        let mut active: ActiveModel = self.into();
+        active.password_hash = Set(password_hash);
+        active.must_change_password = Set(true);
+        let model = active.update(&txn).await?;
+        super::student_refresh_token::Model::revoke_all_with_db(model.id, &txn).await?;
+        txn.commit().await?;
+        Ok(model)
     }
 }
 
@@ -105,13 +168,14 @@ impl ActiveModel {
         account: Option<String>,
         password: String,
         name: Option<String>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self> {
         let password_hash = hash_password(password).await?;
         Ok(Self {
             student_id: Set(student_id),
             account: Set(account),
             password_hash: Set(password_hash),
             name: Set(name),
+            must_change_password: Set(false),
             id: NotSet,
         })
     }
@@ -122,9 +186,7 @@ pub enum Relation {}
 
 impl RelationTrait for Relation {
     fn def(&self) -> RelationDef {
-        match self {
-            _ => panic!("unreachable"),
-        }
+        match *self {}
     }
 }
 
@@ -156,3 +218,62 @@ impl Related<super::experiment::Entity> for Entity {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::{
+        models::student_refresh_token,
+        testing::{test_db, StudentBuilder},
+    };
+
+    #[tokio::test]
+    async fn test_change_and_reset_password() {
+        let db = test_db().await;
+        let student = StudentBuilder::new().password("2021001").insert(&db).await;
+
+        assert!(matches!(
+            student
+                .clone()
+                .change_password_with_db("wrong".to_string(), "new-password".to_string(), &db)
+                .await,
+            Err(Error::InvalidCredentials)
+        ));
+        assert!(matches!(
+            student
+                .clone()
+                .change_password_with_db("2021001".to_string(), "short".to_string(), &db)
+                .await,
+            Err(Error::BadRequest(_))
+        ));
+
+        student_refresh_token::Model::issue_with_db(student.id, 3600, &db)
+            .await
+            .unwrap();
+        let student = student
+            .reset_password_with_db("reset-password".to_string(), &db)
+            .await
+            .unwrap();
+        assert!(student.must_change_password);
+        assert!(student
+            .verify_password("reset-password".to_string())
+            .is_ok());
+        let tokens = student_refresh_token::Entity::find()
+            .filter(student_refresh_token::Column::StudentPid.eq(student.id))
+            .all(&db)
+            .await
+            .unwrap();
+        assert!(tokens.is_empty());
+
+        let student = student
+            .change_password_with_db(
+                "reset-password".to_string(),
+                "new-password".to_string(),
+                &db,
+            )
+            .await
+            .unwrap();
+        assert!(!student.must_change_password);
+        assert!(student.verify_password("new-password".to_string()).is_ok());
+    }
+}
@@ -0,0 +1,243 @@
+// 学生的刷新令牌, 数据库中只保存令牌的哈希
+
+use chrono::{Duration, Utc};
+use sea_orm::{entity::prelude::*, sea_query::Expr, ActiveValue::NotSet, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    db::token::{generate_refresh_token, hash_refresh_token},
+    error::{Error, Result},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "student_refresh_token")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 学生主键
+    pub student_pid: i64,
+    // 令牌的sha256哈希
+    #[sea_orm(unique)]
+    pub token_hash: String,
+    // 令牌族, 同一次登录轮换出的所有令牌属于同一族
+    pub family: String,
+    // 被轮换(使用)的时间, 为空表示尚未使用
+    pub consumed_at: Option<DateTimeUtc>,
+    // 过期时间
+    pub expires_at: DateTimeUtc,
+    // 签发时间
+    pub created_at: DateTimeUtc,
+}
+
+impl Model {
+    // 签发新的刷新令牌, 返回令牌明文和保存的记录
+    pub async fn issue_with_db<C>(
+        student_pid: i64,
+        lifetime_secs: i64,
+        db: &C,
+    ) -> Result<(String, Self)>
+    where
+        C: ConnectionTrait,
+    {
+        let family = Uuid::now_v7().to_string();
+        Self::issue_in_family_with_db(student_pid, family, lifetime_secs, db).await
+    }
+
+    async fn issue_in_family_with_db<C>(
+        student_pid: i64,
+        family: String,
+        lifetime_secs: i64,
+        db: &C,
+    ) -> Result<(String, Self)>
+    where
+        C: ConnectionTrait,
+    {
+        let token = generate_refresh_token();
+        let model = ActiveModel::new(
+            student_pid,
+            hash_refresh_token(&token),
+            family,
+            lifetime_secs,
+        )
+        .insert(db)
+        .await?;
+        Ok((token, model))
+    }
+
+    // 使用刷新令牌换取新的刷新令牌, 旧令牌标记为已使用
+    // 已使用的令牌再次出现说明令牌可能被盗, 此时吊销整个令牌族
+    pub async fn rotate_with_db<C>(
+        token: &str,
+        lifetime_secs: i64,
+        db: &C,
+    ) -> Result<(String, Self)>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let current = Self::find_by_token_with_db(token, db)
+            .await?
+            .ok_or(Error::Unauthorized("Invalid refresh token".to_string()))?;
+
+        if current.consumed_at.is_some() {
+            Self::revoke_family_with_db(&current.family, db).await?;
+            return Err(Error::Unauthorized(
+                "Refresh token reuse detected".to_string(),
+            ));
+        }
+        if current.is_expired() {
+            return Err(Error::Unauthorized("Refresh token expired".to_string()));
+        }
+
+        let txn = db.begin().await?;
+        // 条件更新保证并发请求中只有一个能消费同一个令牌
+        let consumed = Entity::update_many()
+            .col_expr(Column::ConsumedAt, Expr::value(Utc::now()))
+            .filter(Column::Id.eq(current.id))
+            .filter(Column::ConsumedAt.is_null())
+            .exec(&txn)
+            .await?;
+        if consumed.rows_affected == 0 {
+            txn.rollback().await?;
+            Self::revoke_family_with_db(&current.family, db).await?;
+            return Err(Error::Unauthorized(
+                "Refresh token reuse detected".to_string(),
+            ));
+        }
+        let rotated =
+            Self::issue_in_family_with_db(current.student_pid, current.family, lifetime_secs, &txn)
+                .await?;
+        txn.commit().await?;
+        Ok(rotated)
+    }
+
+    // 吊销某个用户的所有刷新令牌
+    pub async fn revoke_all_with_db<C>(student_pid: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::delete_many()
+            .filter(Column::StudentPid.eq(student_pid))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn revoke_family_with_db<C>(family: &str, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::delete_many()
+            .filter(Column::Family.eq(family))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_token_with_db<C>(token: &str, db: &C) -> Result<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::find()
+            .filter(Column::TokenHash.eq(hash_refresh_token(token)))
+            .one(db)
+            .await
+            .map_err(Error::from)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+impl ActiveModel {
+    pub fn new(student_pid: i64, token_hash: String, family: String, lifetime_secs: i64) -> Self {
+        let now = Utc::now();
+        Self {
+            id: NotSet,
+            student_pid: Set(student_pid),
+            token_hash: Set(token_hash),
+            family: Set(family),
+            consumed_at: Set(None),
+            expires_at: Set(now + Duration::seconds(lifetime_secs)),
+            created_at: Set(now),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Student,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Student => Entity::belongs_to(super::student::Entity)
+                .from(Column::StudentPid)
+                .to(super::student::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::student::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Student.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::testing::{test_db, StudentBuilder};
+
+    #[tokio::test]
+    async fn test_rotate_keeps_family() {
+        let db = test_db().await;
+        let student = StudentBuilder::new().insert(&db).await;
+        let (token, issued) = Model::issue_with_db(student.id, 3600, &db).await.unwrap();
+
+        let (rotated_token, rotated) = Model::rotate_with_db(&token, 3600, &db).await.unwrap();
+        assert_ne!(rotated_token, token);
+        assert_eq!(rotated.family, issued.family);
+        assert_eq!(rotated.student_pid, student.id);
+
+        let consumed = Model::find_by_token_with_db(&token, &db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(consumed.consumed_at.is_some());
+        assert!(Model::rotate_with_db(&rotated_token, 3600, &db)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reuse_revokes_family() {
+        let db = test_db().await;
+        let student = StudentBuilder::new().insert(&db).await;
+        let (token, _) = Model::issue_with_db(student.id, 3600, &db).await.unwrap();
+        // 同一学生另一次登录的令牌不受影响
+        let (other_token, _) = Model::issue_with_db(student.id, 3600, &db).await.unwrap();
+        let (rotated_token, _) = Model::rotate_with_db(&token, 3600, &db).await.unwrap();
+
+        assert!(matches!(
+            Model::rotate_with_db(&token, 3600, &db).await,
+            Err(Error::Unauthorized(_))
+        ));
+        assert!(Model::find_by_token_with_db(&token, &db)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            Model::rotate_with_db(&rotated_token, 3600, &db).await,
+            Err(Error::Unauthorized(_))
+        ));
+        assert!(Model::rotate_with_db(&other_token, 3600, &db).await.is_ok());
+    }
+}
@@ -0,0 +1,199 @@
+// 学生的违约记录, 逾期取消和缺席都会按实验的取消规则记录违约次数
+
+use chrono::{Duration, Utc};
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+
+use super::student_suspension;
+use crate::{config::ReservationConfig, error::Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum StrikeReason {
+    // 免费取消期限之后取消
+    #[sea_orm(string_value = "late_cancel")]
+    LateCancel,
+    // 预约后未到场
+    #[sea_orm(string_value = "no_show")]
+    NoShow,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "student_strike")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 学生主键
+    pub student_pid: i64,
+    // 违约的时间段
+    pub time_range_pid: i64,
+    // 违约原因
+    pub reason: StrikeReason,
+    // 记录的违约次数, 规则设置为0时只留记录不计数
+    pub strikes: i32,
+    // 记录时间
+    pub created_at: DateTimeUtc,
+}
+
+// 记录一次违约的结果, 达到阈值时同时返回新生成的暂停
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StrikeOutcome {
+    pub strike: Model,
+    pub suspension: Option<student_suspension::Model>,
+}
+
+// 学生当前的违约情况
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standing {
+    // 上次暂停之后累计的违约次数
+    pub strikes: i32,
+    pub threshold: i32,
+    pub suspended_until: Option<DateTimeUtc>,
+    pub history: Vec<Model>,
+}
+
+impl Model {
+    pub async fn find_with_db<C>(
+        student_pid: i64,
+        time_range_pid: i64,
+        reason: StrikeReason,
+        db: &C,
+    ) -> Result<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .filter(Column::StudentPid.eq(student_pid))
+            .filter(Column::TimeRangePid.eq(time_range_pid))
+            .filter(Column::Reason.eq(reason))
+            .one(db)
+            .await?)
+    }
+
+    pub async fn list_with_db<C>(student_pid: i64, db: &C) -> Result<Vec<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .filter(Column::StudentPid.eq(student_pid))
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?)
+    }
+
+    // 最近一次暂停之后累计的违约次数, 每次暂停后重新计数
+    pub async fn active_strikes_with_db<C>(student_pid: i64, db: &C) -> Result<i32>
+    where
+        C: ConnectionTrait,
+    {
+        let mut query = Entity::find().filter(Column::StudentPid.eq(student_pid));
+        if let Some(suspension) = student_suspension::Model::latest_with_db(student_pid, db).await?
+        {
+            query = query.filter(Column::CreatedAt.gt(suspension.created_at));
+        }
+        Ok(query
+            .all(db)
+            .await?
+            .iter()
+            .map(|strike| strike.strikes)
+            .sum())
+    }
+
+    // 记录违约, 累计次数达到阈值时暂停预约资格
+    pub async fn record_with_db<C>(
+        student_pid: i64,
+        time_range_pid: i64,
+        reason: StrikeReason,
+        strikes: i32,
+        config: &ReservationConfig,
+        db: &C,
+    ) -> Result<StrikeOutcome>
+    where
+        C: ConnectionTrait,
+    {
+        let strike = ActiveModel {
+            id: NotSet,
+            student_pid: Set(student_pid),
+            time_range_pid: Set(time_range_pid),
+            reason: Set(reason),
+            strikes: Set(strikes),
+            created_at: Set(Utc::now()),
+        }
+        .insert(db)
+        .await?;
+        let mut suspension = None;
+        if strikes > 0
+            && Self::active_strikes_with_db(student_pid, db).await? >= config.strike_threshold
+        {
+            let until = Utc::now() + Duration::days(config.suspension_days);
+            tracing::info!(
+                "Suspending booking of student {} until {}",
+                student_pid,
+                until
+            );
+            suspension = Some(
+                student_suspension::ActiveModel::new(student_pid, until)
+                    .insert(db)
+                    .await?,
+            );
+        }
+        Ok(StrikeOutcome { strike, suspension })
+    }
+
+    pub async fn standing_with_db<C>(
+        student_pid: i64,
+        config: &ReservationConfig,
+        db: &C,
+    ) -> Result<Standing>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Standing {
+            strikes: Self::active_strikes_with_db(student_pid, db).await?,
+            threshold: config.strike_threshold,
+            suspended_until: student_suspension::Model::current_with_db(student_pid, db)
+                .await?
+                .map(|suspension| suspension.until),
+            history: Self::list_with_db(student_pid, db).await?,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Student,
+    TimeRange,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Student => Entity::belongs_to(super::student::Entity)
+                .from(Column::StudentPid)
+                .to(super::student::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+            Relation::TimeRange => Entity::belongs_to(super::experiment_time_ranges::Entity)
+                .from(Column::TimeRangePid)
+                .to(super::experiment_time_ranges::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::student::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Student.def()
+    }
+}
+
+impl Related<super::experiment_time_ranges::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TimeRange.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
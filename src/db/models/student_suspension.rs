@@ -0,0 +1,116 @@
+// 学生的预约资格暂停记录, 违约次数达到阈值后生成
+// 解除暂停时只修改结束时间, 记录保留下来作为违约重新计数的起点
+
+use chrono::Utc;
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "student_suspension")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 学生主键
+    pub student_pid: i64,
+    // 开始暂停的时间
+    pub created_at: DateTimeUtc,
+    // 在此时间之前不能预约
+    pub until: DateTimeUtc,
+}
+
+impl Model {
+    // 最近一次暂停, 无论是否已经结束
+    pub async fn latest_with_db<C>(student_pid: i64, db: &C) -> Result<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .filter(Column::StudentPid.eq(student_pid))
+            .order_by_desc(Column::CreatedAt)
+            .order_by_desc(Column::Id)
+            .one(db)
+            .await?)
+    }
+
+    // 仍在生效的暂停
+    pub async fn current_with_db<C>(student_pid: i64, db: &C) -> Result<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .filter(Column::StudentPid.eq(student_pid))
+            .filter(Column::Until.gt(Utc::now()))
+            .order_by_desc(Column::Until)
+            .one(db)
+            .await?)
+    }
+
+    pub async fn ensure_not_suspended_with_db<C>(student_pid: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        match Self::current_with_db(student_pid, db).await? {
+            Some(suspension) => Err(Error::Forbidden(format!(
+                "Booking is suspended until {}",
+                suspension.until.to_rfc3339()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    // 由管理人员提前解除暂停
+    pub async fn lift_with_db<C>(student_pid: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let lifted = Entity::update_many()
+            .col_expr(Column::Until, Expr::value(Utc::now()))
+            .filter(Column::StudentPid.eq(student_pid))
+            .filter(Column::Until.gt(Utc::now()))
+            .exec(db)
+            .await?;
+        if lifted.rows_affected == 0 {
+            return Err(Error::not_found("Suspension"));
+        }
+        Ok(())
+    }
+}
+
+impl ActiveModel {
+    pub fn new(student_pid: i64, until: DateTimeUtc) -> Self {
+        Self {
+            id: NotSet,
+            student_pid: Set(student_pid),
+            created_at: Set(Utc::now()),
+            until: Set(until),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Student,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Student => Entity::belongs_to(super::student::Entity)
+                .from(Column::StudentPid)
+                .to(super::student::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::student::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Student.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,9 +1,32 @@
-use crate::db::{db_conn::get_db_str_result, hash_password, ARGON2};
-use anyhow::Result;
-use argon2::password_hash::{PasswordHash, PasswordVerifier};
-use sea_orm::{entity::prelude::*, ActiveValue::NotSet, Condition, Set};
+use crate::{
+    db::{argon2, hash_password, password_needs_rehash, validate_new_password},
+    error::{Error, Result},
+};
+use argon2::password_hash::{self, PasswordHash, PasswordVerifier};
+use sea_orm::{
+    entity::prelude::*, ActiveValue::NotSet, Condition, Set, TransactionTrait, TryIntoModel,
+};
 use serde::{Deserialize, Serialize};
 
+// 教师账号的全局角色, 班级管理员身份由class_teacher_junction决定
+#[derive(
+    Default, Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum TeacherRole {
+    // 普通教师
+    #[default]
+    #[sea_orm(string_value = "teacher")]
+    Teacher,
+    // 实验室管理人员
+    #[sea_orm(string_value = "lab_staff")]
+    LabStaff,
+    // 系统管理员
+    #[sea_orm(string_value = "system_admin")]
+    SystemAdmin,
+}
+
 #[derive(Default, Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "teacher")]
 pub struct Model {
@@ -18,10 +41,14 @@ pub struct Model {
     pub password_hash: String,
     // 姓名
     pub name: Option<String>,
+    // 需要修改密码后才能使用其他接口, 被重置密码的账号会设置该标记
+    pub must_change_password: bool,
+    // 角色
+    pub role: TeacherRole,
 }
 
 impl Model {
-    pub async fn into_active_model_encrypted(self) -> Result<ActiveModel, String> {
+    pub async fn into_active_model_encrypted(self) -> Result<ActiveModel> {
         let password_hash = hash_password(self.password_hash).await?;
         Ok(ActiveModel {
             id: NotSet,
@@ -29,81 +56,141 @@ impl Model {
             account: Set(self.account),
             password_hash: Set(password_hash),
             name: Set(self.name),
+            must_change_password: Set(self.must_change_password),
+            role: Set(self.role),
         })
     }
 
-    pub async fn join_class_with_db<C>(
-        &self,
-        class_pid: i64,
-        admin: bool,
-        db: &C,
-    ) -> Result<(), String>
+    pub async fn join_class_with_db<C>(&self, class_pid: i64, admin: bool, db: &C) -> Result<()>
     where
         C: ConnectionTrait,
     {
-        let junction =
-            super::class_teacher_junction::ActiveModel::new(class_pid, self.id.clone(), admin);
-        junction
-            .insert(db)
-            .await
-            .map_err(|e| format!("Failed to join class with db: {:?}", e))?;
-        Ok(())
-    }
-
-    pub async fn join_class(&self, class_pid: i64, admin: bool) -> Result<(), String> {
-        let junction =
-            super::class_teacher_junction::ActiveModel::new(class_pid, self.id.clone(), admin);
-        let db = get_db_str_result().await?;
-        junction
-            .insert(&db)
-            .await
-            .map_err(|e| format!("Failed to join class: {:?}", e))?;
+        let junction = super::class_teacher_junction::ActiveModel::new(class_pid, self.id, admin);
+        junction.insert(db).await?;
         Ok(())
     }
 
-    pub async fn leave_class(&self, class_pid: i64) -> Result<(), String> {
-        let db = get_db_str_result().await?;
-        let junction =
-            super::class_teacher_junction::Entity::find_by_id((class_pid, self.id.clone()))
-                .one(&db)
-                .await
-                .map_err(|e| format!("Failed to find junction: {:?}", e))?;
-
-        if let Some(junction) = junction {
-            junction
-                .delete(&db)
-                .await
-                .map_err(|e| format!("Failed to delete junction: {:?}", e))?;
-            Ok(())
-        } else {
-            return Err("Teacher is not in the class".to_string());
+    pub async fn leave_class_with_db<C>(&self, class_pid: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        let deleted = super::class_teacher_junction::Entity::delete_by_id((class_pid, self.id))
+            .exec(db)
+            .await?;
+        if deleted.rows_affected == 0 {
+            return Err(Error::not_found("Class membership"));
         }
+        Ok(())
     }
 
-    pub async fn find_by_teacher_id_or_account(
+    pub async fn find_by_teacher_id_or_account_with_db<C>(
         teacher_id_or_account: String,
-    ) -> Result<Self, String> {
-        let db = get_db_str_result().await?;
-
+        db: &C,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
         Entity::find()
             .filter(
                 Condition::any()
                     .add(Column::TeacherId.eq(teacher_id_or_account.clone()))
                     .add(Column::Account.eq(teacher_id_or_account)),
             )
-            .one(&db)
-            .await
-            .map_err(|e| format!("{:?}", e))?
-            .ok_or("Teacher not found".to_string())
+            .one(db)
+            .await?
+            .ok_or(Error::not_found("Teacher"))
+    }
+
+    // 按账号创建系统管理员, 账号已存在时只提升其角色, 不修改密码
+    pub async fn ensure_system_admin_with_db<C>(
+        account: String,
+        password: String,
+        db: &C,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let existing = Entity::find()
+            .filter(Column::Account.eq(account.clone()))
+            .one(db)
+            .await?;
+        let mut active = match existing {
+            Some(teacher) if teacher.role == TeacherRole::SystemAdmin => return Ok(teacher),
+            Some(teacher) => teacher.into(),
+            None => ActiveModel::new_encrypted(None, Some(account), password, None).await?,
+        };
+        active.role = Set(TeacherRole::SystemAdmin);
+        Ok(active.save(db).await?.try_into_model()?)
     }
 
-    pub fn verify_password(&self, password: String) -> Result<(), String> {
-        let password_hash = PasswordHash::new(&self.password_hash)
-            .map_err(|e| format!("Failed to create PasswordHash: {:?}", e))?;
+    pub fn verify_password(&self, password: String) -> Result<()> {
+        let password_hash = PasswordHash::new(&self.password_hash).map_err(Error::internal)?;
 
-        ARGON2
+        argon2()
             .verify_password(password.as_bytes(), &password_hash)
-            .map_err(|e| format!("Invalid password: {:?}", e.to_string()))
+            .map_err(|e| match e {
+                password_hash::Error::Password => Error::InvalidCredentials,
+                e => Error::internal(e),
+            })
+    }
+
+    pub fn needs_rehash(&self) -> bool {
+        password_needs_rehash(&self.password_hash)
+    }
+
+    // 用新密码(或以当前参数重新哈希的原密码)更新密码哈希
+    pub async fn set_password_with_db<C>(self, password: String, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let password_hash = hash_password(password).await?;
+        let mut active: ActiveModel = self.into();
+        active.password_hash = Set(password_hash);
+        Ok(active.update(db).await?)
+    }
+
+    // 本人修改密码, 需要提供原密码, 修改后清除强制修改标记
+    pub async fn change_password_with_db<C>(
+        self,
+        old_password: String,
+        new_password: String,
+        db: &C,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        validate_new_password(&new_password)?;
+        if old_password == new_password {
+            return Err(Error::BadRequest(
+                "New password must differ from the old one".to_string(),
+            ));
+        }
+        let model =
+            tokio::task::spawn_blocking(move || self.verify_password(old_password).map(|_| self))
+                .await
+                .map_err(Error::internal)??;
+        let password_hash = hash_password(new_password).await?;
+        let mut active: ActiveModel = model.into();
+        active.password_hash = Set(password_hash);
+        active.must_change_password = Set(false);
+        Ok(active.update(db).await?)
+    }
+
+    // 由管理员重置密码, 用户下次登录后必须修改密码, 已签发的刷新令牌全部失效
+    pub async fn reset_password_with_db<C>(self, new_password: String, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        validate_new_password(&new_password)?;
+        let password_hash = hash_password(new_password).await?;
+        let txn = db.begin().await?;
+        let mut active: ActiveModel = self.into();
+        active.password_hash = Set(password_hash);
+        active.must_change_password = Set(true);
+        let model = active.update(&txn).await?;
+        super::teacher_refresh_token::Model::revoke_all_with_db(model.id, &txn).await?;
+        txn.commit().await?;
+        Ok(model)
     }
 }
 
@@ -113,13 +200,15 @@ impl ActiveModel {
         account: Option<String>,
         password: String,
         name: Option<String>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self> {
         let password_hash = hash_password(password).await?;
         Ok(Self {
             teacher_id: Set(teacher_id),
             account: Set(account),
             password_hash: Set(password_hash),
             name: Set(name),
+            must_change_password: Set(false),
+            role: Set(TeacherRole::Teacher),
             id: NotSet,
         })
     }
@@ -130,9 +219,7 @@ pub enum Relation {}
 
 impl RelationTrait for Relation {
     fn def(&self) -> RelationDef {
-        match self {
-            _ => panic!("unreachable"),
-        }
+        match *self {}
     }
 }
 
@@ -164,3 +251,34 @@ impl Related<super::experiment::Entity> for Entity {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::testing::{test_db, TeacherBuilder};
+
+    #[tokio::test]
+    async fn test_ensure_system_admin() {
+        let db = test_db().await;
+        let teacher = TeacherBuilder::new().insert(&db).await;
+        let account = teacher.account.clone().unwrap();
+
+        // 已有账号只提升角色, 保留原密码
+        let admin = Model::ensure_system_admin_with_db(account, "ignored".to_string(), &db)
+            .await
+            .unwrap();
+        assert_eq!(admin.id, teacher.id);
+        assert_eq!(admin.role, TeacherRole::SystemAdmin);
+        assert!(admin.verify_password("password".to_string()).is_ok());
+
+        let created = Model::ensure_system_admin_with_db(
+            "root".to_string(),
+            "root-password".to_string(),
+            &db,
+        )
+        .await
+        .unwrap();
+        assert_eq!(created.role, TeacherRole::SystemAdmin);
+        assert!(created.verify_password("root-password".to_string()).is_ok());
+    }
+}
@@ -0,0 +1,192 @@
+// 教师的刷新令牌, 数据库中只保存令牌的哈希
+
+use chrono::{Duration, Utc};
+use sea_orm::{entity::prelude::*, sea_query::Expr, ActiveValue::NotSet, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    db::token::{generate_refresh_token, hash_refresh_token},
+    error::{Error, Result},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "teacher_refresh_token")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 教师主键
+    pub teacher_pid: i64,
+    // 令牌的sha256哈希
+    #[sea_orm(unique)]
+    pub token_hash: String,
+    // 令牌族, 同一次登录轮换出的所有令牌属于同一族
+    pub family: String,
+    // 被轮换(使用)的时间, 为空表示尚未使用
+    pub consumed_at: Option<DateTimeUtc>,
+    // 过期时间
+    pub expires_at: DateTimeUtc,
+    // 签发时间
+    pub created_at: DateTimeUtc,
+}
+
+impl Model {
+    // 签发新的刷新令牌, 返回令牌明文和保存的记录
+    pub async fn issue_with_db<C>(
+        teacher_pid: i64,
+        lifetime_secs: i64,
+        db: &C,
+    ) -> Result<(String, Self)>
+    where
+        C: ConnectionTrait,
+    {
+        let family = Uuid::now_v7().to_string();
+        Self::issue_in_family_with_db(teacher_pid, family, lifetime_secs, db).await
+    }
+
+    async fn issue_in_family_with_db<C>(
+        teacher_pid: i64,
+        family: String,
+        lifetime_secs: i64,
+        db: &C,
+    ) -> Result<(String, Self)>
+    where
+        C: ConnectionTrait,
+    {
+        let token = generate_refresh_token();
+        let model = ActiveModel::new(
+            teacher_pid,
+            hash_refresh_token(&token),
+            family,
+            lifetime_secs,
+        )
+        .insert(db)
+        .await?;
+        Ok((token, model))
+    }
+
+    // 使用刷新令牌换取新的刷新令牌, 旧令牌标记为已使用
+    // 已使用的令牌再次出现说明令牌可能被盗, 此时吊销整个令牌族
+    pub async fn rotate_with_db<C>(
+        token: &str,
+        lifetime_secs: i64,
+        db: &C,
+    ) -> Result<(String, Self)>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let current = Self::find_by_token_with_db(token, db)
+            .await?
+            .ok_or(Error::Unauthorized("Invalid refresh token".to_string()))?;
+
+        if current.consumed_at.is_some() {
+            Self::revoke_family_with_db(&current.family, db).await?;
+            return Err(Error::Unauthorized(
+                "Refresh token reuse detected".to_string(),
+            ));
+        }
+        if current.is_expired() {
+            return Err(Error::Unauthorized("Refresh token expired".to_string()));
+        }
+
+        let txn = db.begin().await?;
+        // 条件更新保证并发请求中只有一个能消费同一个令牌
+        let consumed = Entity::update_many()
+            .col_expr(Column::ConsumedAt, Expr::value(Utc::now()))
+            .filter(Column::Id.eq(current.id))
+            .filter(Column::ConsumedAt.is_null())
+            .exec(&txn)
+            .await?;
+        if consumed.rows_affected == 0 {
+            txn.rollback().await?;
+            Self::revoke_family_with_db(&current.family, db).await?;
+            return Err(Error::Unauthorized(
+                "Refresh token reuse detected".to_string(),
+            ));
+        }
+        let rotated =
+            Self::issue_in_family_with_db(current.teacher_pid, current.family, lifetime_secs, &txn)
+                .await?;
+        txn.commit().await?;
+        Ok(rotated)
+    }
+
+    // 吊销某个用户的所有刷新令牌
+    pub async fn revoke_all_with_db<C>(teacher_pid: i64, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::delete_many()
+            .filter(Column::TeacherPid.eq(teacher_pid))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn revoke_family_with_db<C>(family: &str, db: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::delete_many()
+            .filter(Column::Family.eq(family))
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_token_with_db<C>(token: &str, db: &C) -> Result<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::find()
+            .filter(Column::TokenHash.eq(hash_refresh_token(token)))
+            .one(db)
+            .await
+            .map_err(Error::from)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+impl ActiveModel {
+    pub fn new(teacher_pid: i64, token_hash: String, family: String, lifetime_secs: i64) -> Self {
+        let now = Utc::now();
+        Self {
+            id: NotSet,
+            teacher_pid: Set(teacher_pid),
+            token_hash: Set(token_hash),
+            family: Set(family),
+            consumed_at: Set(None),
+            expires_at: Set(now + Duration::seconds(lifetime_secs)),
+            created_at: Set(now),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Teacher,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Teacher => Entity::belongs_to(super::teacher::Entity)
+                .from(Column::TeacherPid)
+                .to(super::teacher::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::teacher::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Teacher.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
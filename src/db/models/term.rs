@@ -0,0 +1,197 @@
+// 学期, 用于把日期换算为"第几周星期几"
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "term")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 学期名称, 如"2024-2025学年第一学期"
+    pub name: String,
+    // 第一周内的任意一天, 通常是开学日
+    pub start_date: NaiveDate,
+    // 最后一天, 包含当天
+    pub end_date: NaiveDate,
+}
+
+// 某一天在学期中的位置
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeekLabel {
+    pub date: NaiveDate,
+    pub term_pid: i64,
+    pub term_name: String,
+    // 从1开始的教学周
+    pub week: i64,
+    pub weekday: Weekday,
+    // 如"第7周 星期二"
+    pub label: String,
+}
+
+pub fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "星期一",
+        Weekday::Tue => "星期二",
+        Weekday::Wed => "星期三",
+        Weekday::Thu => "星期四",
+        Weekday::Fri => "星期五",
+        Weekday::Sat => "星期六",
+        Weekday::Sun => "星期日",
+    }
+}
+
+impl Model {
+    // 教学周从第一周的星期一开始计算
+    pub fn first_monday(&self) -> NaiveDate {
+        self.start_date - Duration::days(self.start_date.weekday().num_days_from_monday() as i64)
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start_date <= date && date <= self.end_date
+    }
+
+    pub fn week_of(&self, date: NaiveDate) -> Option<i64> {
+        if !self.contains(date) {
+            return None;
+        }
+        Some((date - self.first_monday()).num_days() / 7 + 1)
+    }
+
+    pub fn label(&self, date: NaiveDate) -> Option<WeekLabel> {
+        let week = self.week_of(date)?;
+        Some(WeekLabel {
+            date,
+            term_pid: self.id,
+            term_name: self.name.clone(),
+            week,
+            weekday: date.weekday(),
+            label: format!("第{}周 {}", week, weekday_name(date.weekday())),
+        })
+    }
+
+    pub async fn find_by_date_with_db<C>(date: NaiveDate, db: &C) -> Result<Option<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .filter(Column::StartDate.lte(date))
+            .filter(Column::EndDate.gte(date))
+            .one(db)
+            .await?)
+    }
+
+    pub async fn list_with_db<C>(db: &C) -> Result<Vec<Self>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(Entity::find()
+            .order_by_asc(Column::StartDate)
+            .all(db)
+            .await?)
+    }
+}
+
+impl ActiveModel {
+    // 学期之间不能重叠, 否则同一天会有两个周次
+    pub async fn new_with_db<C>(
+        name: String,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+        db: &C,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        if name.trim().is_empty() {
+            return Err(Error::BadRequest("Term name must not be empty".to_string()));
+        }
+        if start_date >= end_date {
+            return Err(Error::BadRequest(
+                "Term start date must be earlier than end date".to_string(),
+            ));
+        }
+        let overlapping = Entity::find()
+            .filter(Column::StartDate.lte(end_date))
+            .filter(Column::EndDate.gte(start_date))
+            .one(db)
+            .await?;
+        if let Some(term) = overlapping {
+            return Err(Error::Conflict(format!("Term overlaps with {}", term.name)));
+        }
+        Ok(Self {
+            id: NotSet,
+            name: Set(name.trim().to_string()),
+            start_date: Set(start_date),
+            end_date: Set(end_date),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        panic!("No relations")
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::testing::test_db;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_week_label() {
+        // 2024-09-04是星期三, 第一周从2024-09-02星期一开始
+        let term = Model {
+            id: 1,
+            name: "2024-2025学年第一学期".to_string(),
+            start_date: date(9, 4),
+            end_date: date(12, 31),
+        };
+        assert_eq!(term.first_monday(), date(9, 2));
+        assert_eq!(term.week_of(date(9, 3)), None);
+        assert_eq!(term.week_of(date(9, 8)), Some(1));
+        assert_eq!(term.week_of(date(9, 9)), Some(2));
+        let label = term.label(date(10, 15)).unwrap();
+        assert_eq!(label.week, 7);
+        assert_eq!(label.weekday, Weekday::Tue);
+        assert_eq!(label.label, "第7周 星期二");
+    }
+
+    #[tokio::test]
+    async fn test_terms_must_not_overlap() {
+        let db = test_db().await;
+        ActiveModel::new_with_db("秋季学期".to_string(), date(9, 2), date(12, 31), &db)
+            .await
+            .unwrap()
+            .insert(&db)
+            .await
+            .unwrap();
+        assert!(matches!(
+            ActiveModel::new_with_db("冬季学期".to_string(), date(12, 1), date(12, 31), &db).await,
+            Err(Error::Conflict(_))
+        ));
+        let term = Model::find_by_date_with_db(date(10, 1), &db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(term.name, "秋季学期");
+        assert!(Model::find_by_date_with_db(date(8, 1), &db)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
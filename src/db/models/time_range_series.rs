@@ -0,0 +1,247 @@
+// 按重复规则批量生成的时间段系列, 生成的时间段通过series_pid关联到系列
+
+use std::collections::HashSet;
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc, Weekday,
+};
+use sea_orm::{entity::prelude::*, ActiveValue::NotSet, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+// 单个系列最多覆盖的天数
+pub const MAX_SPAN_DAYS: i64 = 366;
+// 单个系列最多生成的时间段数量
+pub const MAX_OCCURRENCES: usize = 500;
+
+// 重复规则, 日期和时刻都按实验室所在时区解释
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecurrenceRule {
+    // 每周的哪几天, 如["Tue", "Thu"]
+    pub weekdays: Vec<Weekday>,
+    // 起止日期, 包含两端
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    // 每天的开始和结束时刻
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    // 人数上限, 为空时使用配置中的默认值
+    #[serde(default)]
+    pub capacity: Option<i32>,
+    // 需要跳过的日期, 如节假日
+    #[serde(default)]
+    pub skip_dates: Vec<NaiveDate>,
+}
+
+// 规则展开后的一次时间段
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Occurrence {
+    // 当地日期
+    pub date: NaiveDate,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+// 把当地日期和时刻转换为UTC时间
+pub fn local_to_utc(date: NaiveDate, time: NaiveTime, tz: FixedOffset) -> DateTime<Utc> {
+    tz.from_local_datetime(&date.and_time(time))
+        .single()
+        .expect("fixed offset has no ambiguous local time")
+        .with_timezone(&Utc)
+}
+
+impl RecurrenceRule {
+    pub fn validate(&self) -> Result<()> {
+        if self.weekdays.is_empty() {
+            return Err(Error::BadRequest(
+                "At least one weekday is required".to_string(),
+            ));
+        }
+        if self.start_date > self.end_date {
+            return Err(Error::BadRequest(
+                "Start date must not be later than end date".to_string(),
+            ));
+        }
+        if self.end_date - self.start_date >= Duration::days(MAX_SPAN_DAYS) {
+            return Err(Error::BadRequest(format!(
+                "Series must not span more than {} days",
+                MAX_SPAN_DAYS
+            )));
+        }
+        if self.start_time >= self.end_time {
+            return Err(Error::BadRequest(
+                "Start time must be earlier than end time".to_string(),
+            ));
+        }
+        if matches!(self.capacity, Some(capacity) if capacity <= 0) {
+            return Err(Error::BadRequest("Capacity must be positive".to_string()));
+        }
+        Ok(())
+    }
+
+    // 按规则展开所有时间段, blackout中的日期和skip_dates一样被跳过
+    pub fn occurrences(
+        &self,
+        tz: FixedOffset,
+        blackout: &HashSet<NaiveDate>,
+    ) -> Result<Vec<Occurrence>> {
+        self.validate()?;
+        let skip: HashSet<NaiveDate> = self.skip_dates.iter().copied().collect();
+        let occurrences: Vec<Occurrence> = self
+            .start_date
+            .iter_days()
+            .take_while(|date| *date <= self.end_date)
+            .filter(|date| self.weekdays.contains(&date.weekday()))
+            .filter(|date| !skip.contains(date) && !blackout.contains(date))
+            .map(|date| Occurrence {
+                date,
+                start_time: local_to_utc(date, self.start_time, tz),
+                end_time: local_to_utc(date, self.end_time, tz),
+            })
+            .collect();
+        if occurrences.len() > MAX_OCCURRENCES {
+            return Err(Error::BadRequest(format!(
+                "Series must not generate more than {} time ranges",
+                MAX_OCCURRENCES
+            )));
+        }
+        Ok(occurrences)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "time_range_series")]
+pub struct Model {
+    // 自增主键
+    #[sea_orm(primary_key, auto_increment = true)]
+    pub id: i64,
+    // 实验主键
+    pub experiment_pid: i64,
+    // 生成时使用的重复规则
+    pub rule: Json,
+    // 创建时间
+    pub created_at: DateTimeUtc,
+}
+
+impl Model {
+    pub async fn find_by_id_with_db<C>(id: i64, db: &C) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        Entity::find_by_id(id)
+            .one(db)
+            .await?
+            .ok_or(Error::not_found("Time range series"))
+    }
+
+    pub fn rule(&self) -> Result<RecurrenceRule> {
+        serde_json::from_value(self.rule.clone()).map_err(Error::internal)
+    }
+}
+
+impl ActiveModel {
+    pub fn new(experiment_pid: i64, rule: &RecurrenceRule) -> Result<Self> {
+        Ok(Self {
+            id: NotSet,
+            experiment_pid: Set(experiment_pid),
+            rule: Set(serde_json::to_value(rule).map_err(Error::internal)?),
+            created_at: Set(Utc::now()),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Experiment,
+    TimeRanges,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Relation::Experiment => Entity::belongs_to(super::experiment::Entity)
+                .from(Column::ExperimentPid)
+                .to(super::experiment::Column::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .into(),
+            Relation::TimeRanges => Entity::has_many(super::experiment_time_ranges::Entity).into(),
+        }
+    }
+}
+
+impl Related<super::experiment_time_ranges::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TimeRanges.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule() -> RecurrenceRule {
+        RecurrenceRule {
+            weekdays: vec![Weekday::Tue, Weekday::Thu],
+            // 2024-09-02是星期一
+            start_date: NaiveDate::from_ymd_opt(2024, 9, 2).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2024, 9, 15).unwrap(),
+            start_time: NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+            capacity: None,
+            skip_dates: vec![NaiveDate::from_ymd_opt(2024, 9, 12).unwrap()],
+        }
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let blackout = HashSet::from([NaiveDate::from_ymd_opt(2024, 9, 3).unwrap()]);
+        let occurrences = rule().occurrences(tz, &blackout).unwrap();
+        let dates: Vec<String> = occurrences
+            .iter()
+            .map(|occurrence| occurrence.date.to_string())
+            .collect();
+        assert_eq!(dates, ["2024-09-05", "2024-09-10"]);
+        // 东八区14:00对应UTC 06:00
+        assert_eq!(
+            occurrences[0].start_time.to_rfc3339(),
+            "2024-09-05T06:00:00+00:00"
+        );
+        assert_eq!(
+            occurrences[0].end_time - occurrences[0].start_time,
+            Duration::hours(2)
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let tz = FixedOffset::east_opt(0).unwrap();
+        let invalid = [
+            RecurrenceRule {
+                weekdays: Vec::new(),
+                ..rule()
+            },
+            RecurrenceRule {
+                end_date: NaiveDate::from_ymd_opt(2024, 9, 1).unwrap(),
+                ..rule()
+            },
+            RecurrenceRule {
+                end_time: NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+                ..rule()
+            },
+            RecurrenceRule {
+                end_date: NaiveDate::from_ymd_opt(2026, 9, 1).unwrap(),
+                ..rule()
+            },
+        ];
+        for rule in invalid {
+            assert!(matches!(
+                rule.occurrences(tz, &HashSet::new()),
+                Err(Error::BadRequest(_))
+            ));
+        }
+    }
+}
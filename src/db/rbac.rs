@@ -0,0 +1,329 @@
+// 基于角色的权限控制
+//
+// 角色:
+// - 系统管理员: 拥有全部权限
+// - 实验室管理人员: 管理开发板, 预约和校历
+// - 班级管理员: 在所管理的班级内管理学生和教师
+// - 实验负责人: 管理自己负责的实验, 登记缺席
+// - 教师: 查看班级名单, 创建实验
+// - 学生: 预约实验时间段
+
+use sea_orm::{ConnectionTrait, EntityTrait};
+use serde::{Deserialize, Serialize};
+
+use super::models::{
+    class_teacher_junction, experiment_teacher_junction,
+    teacher::{self, TeacherRole},
+};
+use crate::error::{Error, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    SystemAdmin,
+    LabStaff,
+    ClassAdmin,
+    ExperimentOwner,
+    Teacher,
+    Student,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    // 管理账号和角色
+    ManageUsers,
+    // 导入学生名单
+    ImportRoster,
+    // 创建班级
+    CreateClass,
+    // 删除班级
+    DeleteClass,
+    // 添加/移除班级学生
+    ManageClassStudents,
+    // 添加/移除班级教师
+    ManageClassTeachers,
+    // 查看班级名单
+    ViewClassRoster,
+    // 重置学生密码
+    ResetStudentPassword,
+    // 创建实验
+    CreateExperiment,
+    // 修改实验和开放时间段
+    ManageExperiment,
+    // 管理开发板
+    ManageBoards,
+    // 为预约分配开发板
+    AssignBoards,
+    // 取消任意学生的预约
+    CancelAnyReservation,
+    // 管理学期和不开放日期
+    ManageCalendar,
+    // 登记学生缺席
+    MarkNoShow,
+    // 提前解除学生的预约暂停
+    LiftSuspension,
+    // 预约时间段
+    ReserveTimeRange,
+}
+
+impl Role {
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Role::SystemAdmin => &[
+                ManageUsers,
+                ImportRoster,
+                CreateClass,
+                DeleteClass,
+                ManageClassStudents,
+                ManageClassTeachers,
+                ViewClassRoster,
+                ResetStudentPassword,
+                CreateExperiment,
+                ManageExperiment,
+                ManageBoards,
+                AssignBoards,
+                CancelAnyReservation,
+                ManageCalendar,
+                MarkNoShow,
+                LiftSuspension,
+            ],
+            Role::LabStaff => &[
+                ViewClassRoster,
+                ManageBoards,
+                AssignBoards,
+                CancelAnyReservation,
+                ManageCalendar,
+                MarkNoShow,
+                LiftSuspension,
+            ],
+            Role::ClassAdmin => &[
+                DeleteClass,
+                ManageClassStudents,
+                ManageClassTeachers,
+                ViewClassRoster,
+                CreateExperiment,
+            ],
+            Role::ExperimentOwner => &[ManageExperiment, MarkNoShow],
+            Role::Teacher => &[CreateClass, ViewClassRoster, CreateExperiment],
+            Role::Student => &[ReserveTimeRange],
+        }
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+impl From<TeacherRole> for Role {
+    fn from(role: TeacherRole) -> Self {
+        match role {
+            TeacherRole::Teacher => Role::Teacher,
+            TeacherRole::LabStaff => Role::LabStaff,
+            TeacherRole::SystemAdmin => Role::SystemAdmin,
+        }
+    }
+}
+
+// 发起请求的用户在当前上下文中拥有的所有角色
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subject {
+    pub roles: Vec<Role>,
+}
+
+impl Subject {
+    pub fn student() -> Self {
+        Self {
+            roles: vec![Role::Student],
+        }
+    }
+
+    pub fn teacher(teacher: &teacher::Model) -> Self {
+        Self {
+            roles: vec![teacher.role.into()],
+        }
+    }
+
+    // 按教师在班级中的身份补充角色, membership为空表示不属于该班级
+    pub fn in_class(self, membership: Option<&class_teacher_junction::Model>) -> Self {
+        match membership {
+            Some(membership) if membership.admin => self.with_role(Role::ClassAdmin),
+            _ => self,
+        }
+    }
+
+    pub async fn teacher_in_class_with_db<C>(
+        teacher: &teacher::Model,
+        class_pid: i64,
+        db: &C,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let membership = class_teacher_junction::Entity::find_by_id((class_pid, teacher.id))
+            .one(db)
+            .await?;
+        Ok(Self::teacher(teacher).in_class(membership.as_ref()))
+    }
+
+    // 按教师是否负责该实验补充角色
+    pub fn for_experiment(self, ownership: Option<&experiment_teacher_junction::Model>) -> Self {
+        match ownership {
+            Some(_) => self.with_role(Role::ExperimentOwner),
+            None => self,
+        }
+    }
+
+    pub async fn teacher_for_experiment_with_db<C>(
+        teacher: &teacher::Model,
+        experiment_pid: i64,
+        db: &C,
+    ) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let ownership =
+            experiment_teacher_junction::Entity::find_by_id((experiment_pid, teacher.id))
+                .one(db)
+                .await?;
+        Ok(Self::teacher(teacher).for_experiment(ownership.as_ref()))
+    }
+
+    pub fn with_role(mut self, role: Role) -> Self {
+        if !self.roles.contains(&role) {
+            self.roles.push(role);
+        }
+        self
+    }
+
+    pub fn can(&self, permission: Permission) -> bool {
+        self.roles.iter().any(|role| role.can(permission))
+    }
+
+    pub fn require(&self, permission: Permission) -> Result<()> {
+        if self.can(permission) {
+            Ok(())
+        } else {
+            Err(Error::Forbidden(format!(
+                "Missing permission {:?}",
+                permission
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_role_permissions() {
+        let admin = Subject {
+            roles: vec![Role::SystemAdmin],
+        };
+        let staff = Subject {
+            roles: vec![Role::LabStaff],
+        };
+        let teacher = Subject {
+            roles: vec![Role::Teacher],
+        };
+        let student = Subject::student();
+
+        assert!(admin.can(Permission::ManageUsers));
+        assert!(admin.can(Permission::CancelAnyReservation));
+        assert!(staff.can(Permission::CancelAnyReservation));
+        assert!(staff.can(Permission::ManageCalendar));
+        assert!(!teacher.can(Permission::ManageCalendar));
+        assert!(!staff.can(Permission::CreateExperiment));
+        assert!(teacher.can(Permission::CreateExperiment));
+        assert!(!teacher.can(Permission::CancelAnyReservation));
+        assert!(!teacher.can(Permission::ManageClassStudents));
+        assert!(teacher
+            .clone()
+            .with_role(Role::ClassAdmin)
+            .can(Permission::ManageClassStudents));
+        assert!(student.can(Permission::ReserveTimeRange));
+        assert!(student.require(Permission::CreateExperiment).is_err());
+    }
+
+    #[test]
+    fn test_class_scoped_permissions() {
+        let teacher = teacher::Model {
+            id: 1,
+            ..Default::default()
+        };
+        let membership = |admin| class_teacher_junction::Model {
+            class_pid: 1,
+            teacher_pid: 1,
+            admin,
+        };
+        let class_admin = Subject::teacher(&teacher).in_class(Some(&membership(true)));
+        let member = Subject::teacher(&teacher).in_class(Some(&membership(false)));
+        let outsider = Subject::teacher(&teacher).in_class(None);
+        let staff = Subject::teacher(&teacher::Model {
+            role: TeacherRole::LabStaff,
+            ..teacher.clone()
+        })
+        .in_class(None);
+        let admin = Subject::teacher(&teacher::Model {
+            role: TeacherRole::SystemAdmin,
+            ..teacher.clone()
+        })
+        .in_class(None);
+
+        let class_management = [
+            Permission::ManageClassStudents,
+            Permission::ManageClassTeachers,
+            Permission::DeleteClass,
+        ];
+        for permission in class_management {
+            assert!(class_admin.can(permission));
+            assert!(admin.can(permission));
+            assert!(!member.can(permission));
+            assert!(!outsider.can(permission));
+            assert!(!staff.can(permission));
+            assert!(!Subject::student().can(permission));
+        }
+
+        for subject in [&class_admin, &member] {
+            assert!(subject.can(Permission::ViewClassRoster));
+            assert!(subject.can(Permission::CreateExperiment));
+            // 实验只能由负责教师管理, 与班级身份无关
+            assert!(!subject.can(Permission::ManageExperiment));
+        }
+        // 班级管理员可以由教师自己创建班级得到, 不能借此重置学生密码
+        assert!(!class_admin.can(Permission::ResetStudentPassword));
+        assert!(admin.can(Permission::ResetStudentPassword));
+        assert!(staff.can(Permission::ViewClassRoster));
+        assert!(!staff.can(Permission::ManageExperiment));
+        assert!(!Subject::student().can(Permission::ViewClassRoster));
+    }
+
+    #[test]
+    fn test_experiment_scoped_permissions() {
+        let teacher = teacher::Model {
+            id: 1,
+            ..Default::default()
+        };
+        let ownership = experiment_teacher_junction::Model {
+            experiment_pid: 1,
+            teacher_pid: 1,
+        };
+        let owner = Subject::teacher(&teacher).for_experiment(Some(&ownership));
+        let other = Subject::teacher(&teacher).for_experiment(None);
+        let admin = Subject::teacher(&teacher::Model {
+            role: TeacherRole::SystemAdmin,
+            ..teacher.clone()
+        })
+        .for_experiment(None);
+
+        assert!(owner.can(Permission::ManageExperiment));
+        assert!(admin.can(Permission::ManageExperiment));
+        assert!(!other.can(Permission::ManageExperiment));
+        assert!(other.can(Permission::CreateExperiment));
+        assert!(owner.can(Permission::MarkNoShow));
+        assert!(!other.can(Permission::MarkNoShow));
+        assert!(!owner.can(Permission::LiftSuspension));
+    }
+}
@@ -0,0 +1,254 @@
+// 把预约和开放时间段转换为日历事件, 用于导出.ics文件和日历订阅
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::{
+    db::{
+        ical::CalendarEvent,
+        models::{
+            board, experiment, experiment_teacher_junction, experiment_time_ranges,
+            experiment_time_ranges_student_junction,
+        },
+    },
+    error::{Error, Result},
+};
+
+const UID_DOMAIN: &str = "fpga_reserve";
+
+async fn experiment_titles_with_db<C>(
+    experiment_pids: impl IntoIterator<Item = i64>,
+    db: &C,
+) -> Result<HashMap<i64, String>>
+where
+    C: ConnectionTrait,
+{
+    Ok(experiment::Entity::find()
+        .filter(experiment::Column::Id.is_in(experiment_pids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|experiment| (experiment.id, experiment.title))
+        .collect())
+}
+
+fn reservation_event(
+    reservation: &experiment_time_ranges_student_junction::Model,
+    time_range: &experiment_time_ranges::Model,
+    title: Option<&String>,
+    board: Option<&board::Model>,
+) -> CalendarEvent {
+    CalendarEvent {
+        uid: format!(
+            "reservation-{}-{}@{}",
+            reservation.time_range_pid, reservation.student_pid, UID_DOMAIN
+        ),
+        start: time_range.start_time,
+        end: time_range.end_time,
+        summary: title.cloned().unwrap_or_else(|| "实验预约".to_string()),
+        description: board
+            .map(|board| format!("开发板: {}\n序列号: {}", board.model, board.serial)),
+        location: board.and_then(|board| board.location.clone()),
+        stamp: reservation.created_at,
+    }
+}
+
+// 学生的全部预约
+pub async fn student_events_with_db<C>(student_pid: i64, db: &C) -> Result<Vec<CalendarEvent>>
+where
+    C: ConnectionTrait,
+{
+    let reservations: Vec<_> = experiment_time_ranges_student_junction::Entity::find()
+        .filter(experiment_time_ranges_student_junction::Column::StudentPid.eq(student_pid))
+        .find_also_related(experiment_time_ranges::Entity)
+        .order_by_asc(experiment_time_ranges::Column::StartTime)
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|(reservation, time_range)| {
+            time_range.map(|time_range| (reservation, time_range))
+        })
+        .collect();
+    let titles = experiment_titles_with_db(
+        reservations
+            .iter()
+            .map(|(_, time_range)| time_range.experiment_pid),
+        db,
+    )
+    .await?;
+    let boards: HashMap<i64, board::Model> = board::Entity::find()
+        .filter(
+            board::Column::Id.is_in(
+                reservations
+                    .iter()
+                    .filter_map(|(reservation, _)| reservation.board_pid),
+            ),
+        )
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|board| (board.id, board))
+        .collect();
+    Ok(reservations
+        .iter()
+        .map(|(reservation, time_range)| {
+            reservation_event(
+                reservation,
+                time_range,
+                titles.get(&time_range.experiment_pid),
+                reservation
+                    .board_pid
+                    .and_then(|board_pid| boards.get(&board_pid)),
+            )
+        })
+        .collect())
+}
+
+// 学生的单个预约
+pub async fn reservation_event_with_db<C>(
+    time_range_pid: i64,
+    student_pid: i64,
+    db: &C,
+) -> Result<CalendarEvent>
+where
+    C: ConnectionTrait,
+{
+    let reservation =
+        experiment_time_ranges_student_junction::Entity::find_by_id((time_range_pid, student_pid))
+            .one(db)
+            .await?
+            .ok_or(Error::not_found("Reservation"))?;
+    let time_range = experiment_time_ranges::Model::find_by_id_with_db(time_range_pid, db).await?;
+    let experiment = experiment::Entity::find_by_id(time_range.experiment_pid)
+        .one(db)
+        .await?;
+    let board = match reservation.board_pid {
+        Some(board_pid) => board::Entity::find_by_id(board_pid).one(db).await?,
+        None => None,
+    };
+    Ok(reservation_event(
+        &reservation,
+        &time_range,
+        experiment.as_ref().map(|experiment| &experiment.title),
+        board.as_ref(),
+    ))
+}
+
+// 教师负责的实验的全部开放时间段
+pub async fn teacher_events_with_db<C>(teacher_pid: i64, db: &C) -> Result<Vec<CalendarEvent>>
+where
+    C: ConnectionTrait,
+{
+    let experiment_pids: Vec<i64> = experiment_teacher_junction::Entity::find()
+        .filter(experiment_teacher_junction::Column::TeacherPid.eq(teacher_pid))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|ownership| ownership.experiment_pid)
+        .collect();
+    let titles = experiment_titles_with_db(experiment_pids.iter().copied(), db).await?;
+    let time_ranges = experiment_time_ranges::Entity::find()
+        .filter(experiment_time_ranges::Column::ExperimentPid.is_in(experiment_pids))
+        .order_by_asc(experiment_time_ranges::Column::StartTime)
+        .all(db)
+        .await?;
+    let now = Utc::now();
+    Ok(time_ranges
+        .into_iter()
+        .map(|time_range| CalendarEvent {
+            uid: format!("time-range-{}@{}", time_range.id, UID_DOMAIN),
+            start: time_range.start_time,
+            end: time_range.end_time,
+            summary: titles
+                .get(&time_range.experiment_pid)
+                .cloned()
+                .unwrap_or_else(|| "实验".to_string()),
+            description: Some(format!(
+                "已预约: {}/{}",
+                time_range.reserved, time_range.capacity
+            )),
+            location: None,
+            // 人数随预约变化, 每次导出都视为最新
+            stamp: now,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::ReservationConfig,
+        db::{
+            ical::render_calendar,
+            testing::{
+                test_db, ExperimentBuilder, StudentBuilder, TeacherBuilder, TimeRangeBuilder,
+            },
+        },
+    };
+    use sea_orm::ActiveModelTrait;
+
+    #[tokio::test]
+    async fn test_student_and_teacher_events() {
+        let db = test_db().await;
+        let student = StudentBuilder::new().insert(&db).await;
+        let other = StudentBuilder::new().insert(&db).await;
+        let teacher = TeacherBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new()
+            .title("数字时钟")
+            .student(&student)
+            .student(&other)
+            .teacher(&teacher)
+            .insert(&db)
+            .await;
+        let time_range = TimeRangeBuilder::new(&experiment)
+            .capacity(2)
+            .insert(&db)
+            .await;
+        let config = ReservationConfig::default();
+        let reservation = time_range
+            .reserve_with_db(student.id, &config, &db)
+            .await
+            .unwrap();
+        let board = board::ActiveModel::new(
+            "EGO1".to_string(),
+            "001".to_string(),
+            Some("A-12".to_string()),
+        )
+        .insert(&db)
+        .await
+        .unwrap();
+        reservation
+            .assign_board_with_db(board.id, &db)
+            .await
+            .unwrap();
+
+        let events = student_events_with_db(student.id, &db).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "数字时钟");
+        assert_eq!(events[0].location.as_deref(), Some("A-12"));
+        assert_eq!(events[0].start, time_range.start_time);
+        assert_eq!(
+            reservation_event_with_db(time_range.id, student.id, &db)
+                .await
+                .unwrap(),
+            events[0]
+        );
+        assert!(student_events_with_db(other.id, &db)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            reservation_event_with_db(time_range.id, other.id, &db).await,
+            Err(Error::NotFound(_))
+        ));
+
+        let events = teacher_events_with_db(teacher.id, &db).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].description.as_deref(), Some("已预约: 1/2"));
+        let calendar = render_calendar("教师日历", &events);
+        assert!(calendar.contains(&format!("UID:time-range-{}@fpga_reserve", time_range.id)));
+    }
+}
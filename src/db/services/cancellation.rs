@@ -0,0 +1,278 @@
+// 按实验的取消规则处理取消和缺席, 违约记录与预约变更在同一事务中完成
+
+use chrono::Utc;
+use sea_orm::{ConnectionTrait, EntityTrait, TransactionTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::ReservationConfig,
+    db::models::{
+        experiment, experiment_time_ranges, experiment_time_ranges_student_junction,
+        experiment_time_ranges_waitlist,
+        student_strike::{self, StrikeOutcome, StrikeReason},
+    },
+    error::{Error, Result},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CancelOutcome {
+    // 递补成功的候补学生
+    pub promoted: Option<experiment_time_ranges_waitlist::Model>,
+    // 逾期取消时记录的违约
+    pub strike: Option<StrikeOutcome>,
+}
+
+// 学生取消自己的预约, 超过免费取消期限时记录违约
+pub async fn cancel_own_reservation_with_db<C>(
+    time_range: &experiment_time_ranges::Model,
+    student_pid: i64,
+    config: &ReservationConfig,
+    db: &C,
+) -> Result<CancelOutcome>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let policy = experiment::Model::find_by_id_with_db(time_range.experiment_pid, db)
+        .await?
+        .cancellation_policy(config);
+    let txn = db.begin().await?;
+    let promoted = time_range
+        .cancel_reservation_with_db(student_pid, &txn)
+        .await?;
+    let strike = if policy.is_late(time_range.start_time, Utc::now()) {
+        Some(
+            student_strike::Model::record_with_db(
+                student_pid,
+                time_range.id,
+                StrikeReason::LateCancel,
+                policy.late_cancel_strikes,
+                config,
+                &txn,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+    txn.commit().await?;
+    Ok(CancelOutcome { promoted, strike })
+}
+
+// 登记缺席, 时间段开始后才能登记, 同一预约只能登记一次
+pub async fn mark_no_show_with_db<C>(
+    time_range: &experiment_time_ranges::Model,
+    student_pid: i64,
+    config: &ReservationConfig,
+    db: &C,
+) -> Result<StrikeOutcome>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if time_range.start_time > Utc::now() {
+        return Err(Error::BadRequest(
+            "Time range has not started yet".to_string(),
+        ));
+    }
+    let policy = experiment::Model::find_by_id_with_db(time_range.experiment_pid, db)
+        .await?
+        .cancellation_policy(config);
+    let txn = db.begin().await?;
+    experiment_time_ranges_student_junction::Entity::find_by_id((time_range.id, student_pid))
+        .one(&txn)
+        .await?
+        .ok_or(Error::not_found("Reservation"))?;
+    if student_strike::Model::find_with_db(student_pid, time_range.id, StrikeReason::NoShow, &txn)
+        .await?
+        .is_some()
+    {
+        return Err(Error::Conflict("No-show is already recorded".to_string()));
+    }
+    let outcome = student_strike::Model::record_with_db(
+        student_pid,
+        time_range.id,
+        StrikeReason::NoShow,
+        policy.no_show_strikes,
+        config,
+        &txn,
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+    use sea_orm::ActiveModelTrait;
+
+    use super::*;
+    use crate::db::{
+        models::{experiment::ExperimentFields, student_suspension},
+        testing::{test_db, ExperimentBuilder, StudentBuilder, TimeRangeBuilder},
+    };
+
+    #[tokio::test]
+    async fn test_late_cancel_uses_experiment_policy() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let student = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new().student(&student).insert(&db).await;
+        // 两天后开始的时间段, 全局规则(提前24小时)下取消不算逾期
+        let slot = TimeRangeBuilder::new(&experiment)
+            .start_time(Utc::now() + Duration::hours(48))
+            .insert(&db)
+            .await;
+        slot.reserve_with_db(student.id, &config, &db)
+            .await
+            .unwrap();
+        let outcome = cancel_own_reservation_with_db(&slot, student.id, &config, &db)
+            .await
+            .unwrap();
+        assert_eq!(outcome.strike, None);
+
+        // 实验要求提前72小时取消
+        let title = experiment.title.clone();
+        experiment
+            .update_with_db(
+                ExperimentFields {
+                    title,
+                    free_cancel_hours: Some(72),
+                    late_cancel_strikes: Some(2),
+                    ..Default::default()
+                },
+                &db,
+            )
+            .await
+            .unwrap();
+        let slot = experiment_time_ranges::Model::find_by_id_with_db(slot.id, &db)
+            .await
+            .unwrap();
+        slot.reserve_with_db(student.id, &config, &db)
+            .await
+            .unwrap();
+        let outcome = cancel_own_reservation_with_db(&slot, student.id, &config, &db)
+            .await
+            .unwrap();
+        let strike = outcome.strike.unwrap();
+        assert_eq!(strike.strike.reason, StrikeReason::LateCancel);
+        assert_eq!(strike.strike.strikes, 2);
+        assert_eq!(strike.suspension, None);
+        assert_eq!(
+            student_strike::Model::active_strikes_with_db(student.id, &db)
+                .await
+                .unwrap(),
+            2
+        );
+        // 取消本身仍然生效
+        assert!(matches!(
+            slot.cancel_reservation_with_db(student.id, &db).await,
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_after_start_rejected() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let student = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new().student(&student).insert(&db).await;
+        let started = TimeRangeBuilder::new(&experiment)
+            .start_time(Utc::now() - Duration::minutes(30))
+            .insert(&db)
+            .await;
+        experiment_time_ranges_student_junction::ActiveModel::new(started.id, student.id)
+            .insert(&db)
+            .await
+            .unwrap();
+
+        // 开始后不能用逾期取消代替缺席
+        assert!(matches!(
+            cancel_own_reservation_with_db(&started, student.id, &config, &db).await,
+            Err(Error::Conflict(_))
+        ));
+        assert!(matches!(
+            started.cancel_reservation_with_db(student.id, &db).await,
+            Err(Error::Conflict(_))
+        ));
+        assert!(student_strike::Model::list_with_db(student.id, &db)
+            .await
+            .unwrap()
+            .is_empty());
+        let outcome = mark_no_show_with_db(&started, student.id, &config, &db)
+            .await
+            .unwrap();
+        assert_eq!(outcome.strike.strikes, config.no_show_strikes);
+    }
+
+    #[tokio::test]
+    async fn test_strikes_suspend_booking() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let student = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new().student(&student).insert(&db).await;
+        let past = TimeRangeBuilder::new(&experiment)
+            .start_time(Utc::now() - Duration::hours(3))
+            .insert(&db)
+            .await;
+        let soon = TimeRangeBuilder::new(&experiment)
+            .start_time(Utc::now() + Duration::hours(2))
+            .insert(&db)
+            .await;
+        let later = TimeRangeBuilder::new(&experiment)
+            .start_time(Utc::now() + Duration::days(3))
+            .insert(&db)
+            .await;
+
+        // 未开始的时间段不能登记缺席
+        assert!(matches!(
+            mark_no_show_with_db(&later, student.id, &config, &db).await,
+            Err(Error::BadRequest(_))
+        ));
+        assert!(matches!(
+            mark_no_show_with_db(&past, student.id, &config, &db).await,
+            Err(Error::NotFound(_))
+        ));
+
+        soon.reserve_with_db(student.id, &config, &db)
+            .await
+            .unwrap();
+        let outcome = cancel_own_reservation_with_db(&soon, student.id, &config, &db)
+            .await
+            .unwrap();
+        assert_eq!(outcome.strike.unwrap().strike.strikes, 1);
+
+        experiment_time_ranges_student_junction::ActiveModel::new(past.id, student.id)
+            .insert(&db)
+            .await
+            .unwrap();
+        let outcome = mark_no_show_with_db(&past, student.id, &config, &db)
+            .await
+            .unwrap();
+        assert_eq!(outcome.strike.strikes, 2);
+        let suspension = outcome.suspension.unwrap();
+        assert!(suspension.until > Utc::now() + Duration::days(config.suspension_days - 1));
+        assert!(matches!(
+            mark_no_show_with_db(&past, student.id, &config, &db).await,
+            Err(Error::Conflict(_))
+        ));
+        assert!(matches!(
+            later.reserve_with_db(student.id, &config, &db).await,
+            Err(Error::Forbidden(_))
+        ));
+
+        // 解除暂停后违约次数重新计数
+        student_suspension::Model::lift_with_db(student.id, &db)
+            .await
+            .unwrap();
+        let standing = student_strike::Model::standing_with_db(student.id, &config, &db)
+            .await
+            .unwrap();
+        assert_eq!(standing.strikes, 0);
+        assert_eq!(standing.suspended_until, None);
+        assert_eq!(standing.history.len(), 2);
+        later
+            .reserve_with_db(student.id, &config, &db)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+// 班级相关的多步操作
+
+use sea_orm::{ActiveModelTrait, ConnectionTrait, EntityTrait, TransactionTrait};
+
+use crate::{
+    db::models::{class, student, teacher},
+    error::{Error, Result},
+};
+
+// 创建班级, 同时设置初始管理员教师和学生名单
+pub async fn create_class_with_db<C>(
+    class_id: Option<String>,
+    admin_pid: i64,
+    student_pids: &[i64],
+    db: &C,
+) -> Result<class::Model>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+    let admin = teacher::Entity::find_by_id(admin_pid)
+        .one(&txn)
+        .await?
+        .ok_or(Error::not_found("Teacher"))?;
+    let class = class::ActiveModel::new(class_id).insert(&txn).await?;
+    admin.join_class_with_db(class.id, true, &txn).await?;
+    for &student_pid in student_pids {
+        let student = student::Entity::find_by_id(student_pid)
+            .one(&txn)
+            .await?
+            .ok_or(Error::NotFound(format!("Student {}", student_pid)))?;
+        student.join_class_with_db(class.id, &txn).await?;
+    }
+    txn.commit().await?;
+    Ok(class)
+}
+
+// 把学生从一个班级转到另一个班级
+pub async fn transfer_student_with_db<C>(
+    student_pid: i64,
+    from_class_pid: i64,
+    to_class_pid: i64,
+    db: &C,
+) -> Result<()>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if from_class_pid == to_class_pid {
+        return Err(Error::BadRequest(
+            "Source and target class must be different".to_string(),
+        ));
+    }
+    let txn = db.begin().await?;
+    let student = student::Entity::find_by_id(student_pid)
+        .one(&txn)
+        .await?
+        .ok_or(Error::not_found("Student"))?;
+    class::Entity::find_by_id(to_class_pid)
+        .one(&txn)
+        .await?
+        .ok_or(Error::not_found("Class"))?;
+    student.leave_class_with_db(from_class_pid, &txn).await?;
+    student.join_class_with_db(to_class_pid, &txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use sea_orm::ModelTrait;
+
+    use super::*;
+    use crate::db::testing::{test_db, ClassBuilder, StudentBuilder, TeacherBuilder};
+
+    #[tokio::test]
+    async fn test_create_class_rolls_back_on_missing_student() {
+        let db = test_db().await;
+        let teacher = TeacherBuilder::new().insert(&db).await;
+        let student = StudentBuilder::new().insert(&db).await;
+
+        let result = create_class_with_db(
+            Some("计科2101".to_string()),
+            teacher.id,
+            &[student.id, 9999],
+            &db,
+        )
+        .await;
+        assert!(matches!(result, Err(Error::NotFound(_))));
+        assert!(class::Entity::find().all(&db).await.unwrap().is_empty());
+
+        let class =
+            create_class_with_db(Some("计科2101".to_string()), teacher.id, &[student.id], &db)
+                .await
+                .unwrap();
+        let students = class.find_related(student::Entity).all(&db).await.unwrap();
+        assert_eq!(students, vec![student]);
+        let teachers = class.find_related(teacher::Entity).all(&db).await.unwrap();
+        assert_eq!(teachers, vec![teacher]);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_student() {
+        let db = test_db().await;
+        let student = StudentBuilder::new().insert(&db).await;
+        let from = ClassBuilder::new().student(&student).insert(&db).await;
+        let to = ClassBuilder::new().insert(&db).await;
+
+        // 目标班级不存在时不会离开原班级
+        assert!(transfer_student_with_db(student.id, from.id, 9999, &db)
+            .await
+            .is_err());
+        assert_eq!(
+            student.find_related(class::Entity).all(&db).await.unwrap(),
+            vec![from.clone()]
+        );
+
+        transfer_student_with_db(student.id, from.id, to.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(
+            student.find_related(class::Entity).all(&db).await.unwrap(),
+            vec![to]
+        );
+    }
+}
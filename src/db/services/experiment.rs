@@ -0,0 +1,311 @@
+// 实验相关的多步操作
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, TransactionTrait,
+};
+
+use crate::{
+    db::models::{
+        class, class_student_junction,
+        experiment::{self, ExperimentFields},
+        experiment_class_junction, experiment_student_junction, experiment_teacher_junction,
+        teacher,
+    },
+    error::{Error, Result},
+};
+
+// 创建实验, 创建者成为负责教师
+pub async fn create_experiment_with_db<C>(
+    fields: ExperimentFields,
+    owner_pid: i64,
+    db: &C,
+) -> Result<experiment::Model>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    fields.validate()?;
+    let txn = db.begin().await?;
+    teacher::Entity::find_by_id(owner_pid)
+        .one(&txn)
+        .await?
+        .ok_or(Error::not_found("Teacher"))?;
+    let experiment = experiment::ActiveModel::from_fields(fields)
+        .insert(&txn)
+        .await?;
+    experiment_teacher_junction::ActiveModel::new(experiment.id, owner_pid)
+        .insert(&txn)
+        .await?;
+    txn.commit().await?;
+    Ok(experiment)
+}
+
+// 把实验布置给班级, 班级现有学生全部选修, 之后加入班级的学生会自动选修
+pub async fn assign_class_with_db<C>(experiment_pid: i64, class_pid: i64, db: &C) -> Result<()>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+    let experiment = experiment::Model::find_by_id_with_db(experiment_pid, &txn).await?;
+    if experiment.archived {
+        return Err(Error::Conflict(
+            "Archived experiment cannot be assigned".to_string(),
+        ));
+    }
+    class::Entity::find_by_id(class_pid)
+        .one(&txn)
+        .await?
+        .ok_or(Error::not_found("Class"))?;
+    if experiment_class_junction::Entity::find_by_id((experiment_pid, class_pid))
+        .one(&txn)
+        .await?
+        .is_some()
+    {
+        return Err(Error::Conflict(
+            "Experiment is already assigned to the class".to_string(),
+        ));
+    }
+    experiment_class_junction::ActiveModel::new(experiment_pid, class_pid)
+        .insert(&txn)
+        .await?;
+    let members = class_student_junction::Entity::find()
+        .filter(class_student_junction::Column::ClassPid.eq(class_pid))
+        .all(&txn)
+        .await?;
+    experiment_student_junction::Model::enroll_many_with_db(
+        experiment_pid,
+        members.into_iter().map(|member| member.student_pid),
+        &txn,
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+// 取消布置, 已经选修的学生保留选修记录和预约
+pub async fn unassign_class_with_db<C>(experiment_pid: i64, class_pid: i64, db: &C) -> Result<()>
+where
+    C: ConnectionTrait,
+{
+    let deleted = experiment_class_junction::Entity::delete_by_id((experiment_pid, class_pid))
+        .exec(db)
+        .await?;
+    if deleted.rows_affected == 0 {
+        return Err(Error::not_found("Experiment assignment"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Duration, Utc};
+
+    use sea_orm::{DatabaseConnection, ModelTrait};
+
+    use super::*;
+    use crate::db::{
+        models::student,
+        testing::{test_db, ClassBuilder, ExperimentBuilder, StudentBuilder, TeacherBuilder},
+    };
+
+    #[tokio::test]
+    async fn test_create_and_update_experiment() {
+        let db = test_db().await;
+        let owner = TeacherBuilder::new().insert(&db).await;
+        let other = TeacherBuilder::new().insert(&db).await;
+
+        let fields = ExperimentFields {
+            title: " 数字时钟 ".to_string(),
+            board_model: Some("EGO1".to_string()),
+            duration_minutes: Some(120),
+            deadline: Some(Utc::now() + Duration::days(30)),
+            ..Default::default()
+        };
+        let experiment = create_experiment_with_db(fields.clone(), owner.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(experiment.title, "数字时钟");
+        assert!(experiment.is_open());
+        assert!(experiment.is_owned_by_with_db(owner.id, &db).await.unwrap());
+        assert!(!experiment.is_owned_by_with_db(other.id, &db).await.unwrap());
+        let owned = experiment::Model::list_by_teacher_with_db(owner.id, false, &db)
+            .await
+            .unwrap();
+        assert_eq!(owned, vec![experiment.clone()]);
+
+        // 非法字段不会写入数据库
+        let invalid = ExperimentFields {
+            duration_minutes: Some(0),
+            ..fields.clone()
+        };
+        assert!(matches!(
+            experiment.clone().update_with_db(invalid, &db).await,
+            Err(Error::BadRequest(_))
+        ));
+
+        let experiment = experiment
+            .update_with_db(
+                ExperimentFields {
+                    description: Some("使用七段数码管显示时间".to_string()),
+                    ..fields
+                },
+                &db,
+            )
+            .await
+            .unwrap();
+        assert!(experiment.description.is_some());
+
+        // 归档后不能修改, 也不出现在默认列表中
+        let experiment = experiment.set_archived_with_db(true, &db).await.unwrap();
+        assert!(!experiment.is_open());
+        assert!(experiment::Model::list_with_db(false, &db)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            experiment::Model::list_with_db(true, &db)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(matches!(
+            experiment
+                .update_with_db(
+                    ExperimentFields {
+                        title: "新名称".to_string(),
+                        ..Default::default()
+                    },
+                    &db
+                )
+                .await,
+            Err(Error::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_experiment_owners() {
+        let db = test_db().await;
+        let owner = TeacherBuilder::new().insert(&db).await;
+        let other = TeacherBuilder::new().insert(&db).await;
+
+        let experiment = create_experiment_with_db(
+            ExperimentFields {
+                title: "流水灯".to_string(),
+                ..Default::default()
+            },
+            owner.id,
+            &db,
+        )
+        .await
+        .unwrap();
+        // 唯一的负责教师不能移除
+        assert!(matches!(
+            experiment.remove_owner_with_db(owner.id, &db).await,
+            Err(Error::Conflict(_))
+        ));
+
+        experiment.add_owner_with_db(other.id, &db).await.unwrap();
+        assert!(matches!(
+            experiment.add_owner_with_db(other.id, &db).await,
+            Err(Error::Conflict(_))
+        ));
+        experiment
+            .remove_owner_with_db(owner.id, &db)
+            .await
+            .unwrap();
+        assert!(!experiment.is_owned_by_with_db(owner.id, &db).await.unwrap());
+        assert!(experiment.is_owned_by_with_db(other.id, &db).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_create_experiment_validates_fields() {
+        let db = test_db().await;
+        let owner = TeacherBuilder::new().insert(&db).await;
+        for fields in [
+            ExperimentFields {
+                title: "  ".to_string(),
+                ..Default::default()
+            },
+            ExperimentFields {
+                title: "实验".to_string(),
+                board_model: Some(String::new()),
+                ..Default::default()
+            },
+            ExperimentFields {
+                title: "长".repeat(ExperimentFields::MAX_TITLE_LEN + 1),
+                ..Default::default()
+            },
+        ] {
+            assert!(matches!(
+                create_experiment_with_db(fields, owner.id, &db).await,
+                Err(Error::BadRequest(_))
+            ));
+        }
+        assert!(experiment::Model::list_with_db(true, &db)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    async fn enrolled(experiment: &experiment::Model, db: &DatabaseConnection) -> Vec<i64> {
+        let mut pids: Vec<i64> = experiment
+            .find_related(student::Entity)
+            .all(db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|student| student.id)
+            .collect();
+        pids.sort();
+        pids
+    }
+
+    #[tokio::test]
+    async fn test_assign_class_enrolls_students() {
+        let db = test_db().await;
+        let alice = StudentBuilder::new().insert(&db).await;
+        let bob = StudentBuilder::new().insert(&db).await;
+        let carol = StudentBuilder::new().insert(&db).await;
+        let class = ClassBuilder::new()
+            .student(&alice)
+            .student(&bob)
+            .insert(&db)
+            .await;
+        // bob已经单独选修过, 布置时不会重复选修
+        let experiment = ExperimentBuilder::new().student(&bob).insert(&db).await;
+
+        assign_class_with_db(experiment.id, class.id, &db)
+            .await
+            .unwrap();
+        assert!(matches!(
+            assign_class_with_db(experiment.id, class.id, &db).await,
+            Err(Error::Conflict(_))
+        ));
+        assert_eq!(enrolled(&experiment, &db).await, vec![alice.id, bob.id]);
+
+        // 之后加入班级的学生自动选修
+        carol.join_class_with_db(class.id, &db).await.unwrap();
+        assert_eq!(
+            enrolled(&experiment, &db).await,
+            vec![alice.id, bob.id, carol.id]
+        );
+
+        // 离开班级或取消布置都不会退选
+        alice.leave_class_with_db(class.id, &db).await.unwrap();
+        unassign_class_with_db(experiment.id, class.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(
+            enrolled(&experiment, &db).await,
+            vec![alice.id, bob.id, carol.id]
+        );
+
+        // 取消布置后新加入的学生不再自动选修
+        let dave = StudentBuilder::new().insert(&db).await;
+        dave.join_class_with_db(class.id, &db).await.unwrap();
+        assert_eq!(
+            enrolled(&experiment, &db).await,
+            vec![alice.id, bob.id, carol.id]
+        );
+    }
+}
@@ -0,0 +1,7 @@
+// 跨多个表的业务操作, 每个操作在单个事务中完成, 任一步骤失败时整体回滚
+
+pub mod calendar;
+pub mod cancellation;
+pub mod class;
+pub mod experiment;
+pub mod time_range_series;
@@ -0,0 +1,481 @@
+// 重复时间段系列的生成和批量修改
+
+use chrono::{FixedOffset, NaiveTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, ModelTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::ReservationConfig,
+    db::models::{
+        blackout_date, experiment, experiment_time_ranges, experiment_time_ranges_waitlist,
+        time_range_series::{self, local_to_utc, RecurrenceRule},
+    },
+    error::{Error, Result},
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GeneratedSeries {
+    pub series: time_range_series::Model,
+    pub time_ranges: Vec<experiment_time_ranges::Model>,
+}
+
+// 批量修改系列中尚未开始的时间段, 为空的字段保持不变
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SeriesUpdate {
+    pub capacity: Option<i32>,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+}
+
+// 批量操作的结果
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeriesChange {
+    // 已修改或删除的时间段
+    pub affected: Vec<i64>,
+    // 因已有预约而跳过的时间段
+    pub skipped: Vec<i64>,
+}
+
+// 按规则生成时间段, 已经开始的时间段会被跳过
+pub async fn generate_series_with_db<C>(
+    experiment_pid: i64,
+    rule: RecurrenceRule,
+    config: &ReservationConfig,
+    db: &C,
+) -> Result<GeneratedSeries>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let now = Utc::now();
+    let blackout =
+        blackout_date::Model::dates_between_with_db(rule.start_date, rule.end_date, db).await?;
+    let occurrences: Vec<_> = rule
+        .occurrences(config.timezone(), &blackout)?
+        .into_iter()
+        .filter(|occurrence| occurrence.start_time > now)
+        .collect();
+    if occurrences.is_empty() {
+        return Err(Error::BadRequest(
+            "Rule does not produce any upcoming time range".to_string(),
+        ));
+    }
+    let capacity = rule.capacity.unwrap_or(config.default_capacity);
+
+    let txn = db.begin().await?;
+    let experiment = experiment::Model::find_by_id_with_db(experiment_pid, &txn).await?;
+    if experiment.archived {
+        return Err(Error::Conflict(
+            "Archived experiment cannot be modified".to_string(),
+        ));
+    }
+    let series = time_range_series::ActiveModel::new(experiment.id, &rule)?
+        .insert(&txn)
+        .await?;
+    let mut time_ranges = Vec::with_capacity(occurrences.len());
+    for occurrence in occurrences {
+        let mut time_range = experiment_time_ranges::ActiveModel::new(
+            experiment.id,
+            occurrence.start_time,
+            occurrence.end_time,
+            capacity,
+        )?;
+        time_range.series_pid = Set(Some(series.id));
+        time_ranges.push(time_range.insert(&txn).await?);
+    }
+    txn.commit().await?;
+    Ok(GeneratedSeries {
+        series,
+        time_ranges,
+    })
+}
+
+// 系列中尚未开始的时间段
+async fn upcoming_time_ranges_with_db<C>(
+    series: &time_range_series::Model,
+    db: &C,
+) -> Result<Vec<experiment_time_ranges::Model>>
+where
+    C: ConnectionTrait,
+{
+    Ok(series
+        .find_related(experiment_time_ranges::Entity)
+        .filter(experiment_time_ranges::Column::StartTime.gt(Utc::now()))
+        .order_by_asc(experiment_time_ranges::Column::StartTime)
+        .all(db)
+        .await?)
+}
+
+// 批量修改人数上限或每天的时刻, 新的人数上限小于已预约人数的时间段会被跳过
+pub async fn update_series_with_db<C>(
+    series: &time_range_series::Model,
+    update: SeriesUpdate,
+    tz: FixedOffset,
+    db: &C,
+) -> Result<SeriesChange>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut rule = series.rule()?;
+    rule.capacity = update.capacity.or(rule.capacity);
+    rule.start_time = update.start_time.unwrap_or(rule.start_time);
+    rule.end_time = update.end_time.unwrap_or(rule.end_time);
+    rule.validate()?;
+
+    let txn = db.begin().await?;
+    let mut change = SeriesChange::default();
+    for time_range in upcoming_time_ranges_with_db(series, &txn).await? {
+        if matches!(update.capacity, Some(capacity) if capacity < time_range.reserved) {
+            change.skipped.push(time_range.id);
+            continue;
+        }
+        let date = time_range.start_time.with_timezone(&tz).date_naive();
+        let id = time_range.id;
+        // 调高人数上限后空出的名额
+        let freed = match update.capacity {
+            Some(capacity) if capacity > time_range.capacity => capacity - time_range.reserved,
+            _ => 0,
+        };
+        let mut active: experiment_time_ranges::ActiveModel = time_range.into();
+        if let Some(capacity) = update.capacity {
+            active.capacity = Set(capacity);
+        }
+        if update.start_time.is_some() || update.end_time.is_some() {
+            active.start_time = Set(local_to_utc(date, rule.start_time, tz));
+            active.end_time = Set(local_to_utc(date, rule.end_time, tz));
+        }
+        active.update(&txn).await?;
+        // 在同一事务中按空出的名额依次递补候补学生
+        for _ in 0..freed {
+            if experiment_time_ranges_waitlist::Model::promote_next_with_db(id, &txn)
+                .await?
+                .is_none()
+            {
+                break;
+            }
+        }
+        change.affected.push(id);
+    }
+    // 有时间段被跳过时系列中的时间段不再一致, 保留原规则, 由调用方根据skipped处理
+    if change.skipped.is_empty() {
+        let mut active: time_range_series::ActiveModel = series.clone().into();
+        active.rule = Set(serde_json::to_value(&rule).map_err(Error::internal)?);
+        active.update(&txn).await?;
+    }
+    txn.commit().await?;
+    Ok(change)
+}
+
+// 删除系列中尚未开始且没有预约的时间段, 已经开始或有预约的时间段保留
+pub async fn delete_series_with_db<C>(
+    series: time_range_series::Model,
+    db: &C,
+) -> Result<SeriesChange>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+    let mut change = SeriesChange::default();
+    for time_range in upcoming_time_ranges_with_db(&series, &txn).await? {
+        if time_range.reserved > 0 {
+            change.skipped.push(time_range.id);
+            continue;
+        }
+        change.affected.push(time_range.id);
+        time_range.delete(&txn).await?;
+    }
+    // 系列下没有剩余的时间段时一并删除系列
+    let remaining = series
+        .find_related(experiment_time_ranges::Entity)
+        .one(&txn)
+        .await?;
+    if remaining.is_none() {
+        series.delete(&txn).await?;
+    }
+    txn.commit().await?;
+    Ok(change)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Datelike, Duration, Weekday};
+
+    use super::*;
+    use crate::db::testing::{test_db, ExperimentBuilder, StudentBuilder};
+
+    // 从明天开始的两周, 每周一三五
+    fn rule(tz: FixedOffset) -> RecurrenceRule {
+        let start_date = (Utc::now() + Duration::days(1))
+            .with_timezone(&tz)
+            .date_naive();
+        RecurrenceRule {
+            weekdays: vec![Weekday::Mon, Weekday::Wed, Weekday::Fri],
+            start_date,
+            end_date: start_date + Duration::days(13),
+            start_time: NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+            capacity: None,
+            skip_dates: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_update_and_delete_series() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let tz = config.timezone();
+        let alice = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new().student(&alice).insert(&db).await;
+
+        let generated = generate_series_with_db(experiment.id, rule(tz), &config, &db)
+            .await
+            .unwrap();
+        assert_eq!(generated.time_ranges.len(), 6);
+        for time_range in &generated.time_ranges {
+            assert_eq!(time_range.series_pid, Some(generated.series.id));
+            assert_eq!(time_range.capacity, config.default_capacity);
+            let local = time_range.start_time.with_timezone(&tz);
+            assert!([Weekday::Mon, Weekday::Wed, Weekday::Fri].contains(&local.weekday()));
+        }
+        let booked = &generated.time_ranges[0];
+        booked
+            .reserve_with_db(alice.id, &config, &db)
+            .await
+            .unwrap();
+
+        // 推迟一小时并调整人数上限
+        let change = update_series_with_db(
+            &generated.series,
+            SeriesUpdate {
+                capacity: Some(20),
+                start_time: NaiveTime::from_hms_opt(15, 0, 0),
+                end_time: NaiveTime::from_hms_opt(17, 0, 0),
+            },
+            tz,
+            &db,
+        )
+        .await
+        .unwrap();
+        assert_eq!(change.affected.len(), 6);
+        let moved = experiment_time_ranges::Model::find_by_id_with_db(booked.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(moved.start_time - booked.start_time, Duration::hours(1));
+        assert_eq!(moved.capacity, 20);
+        assert_eq!(moved.reserved, 1);
+        let series = time_range_series::Model::find_by_id_with_db(generated.series.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(series.rule().unwrap().capacity, Some(20));
+
+        // 人数上限不能低于已预约人数
+        let change = update_series_with_db(
+            &series,
+            SeriesUpdate {
+                capacity: Some(0),
+                ..Default::default()
+            },
+            tz,
+            &db,
+        )
+        .await;
+        assert!(matches!(change, Err(Error::BadRequest(_))));
+
+        // 有预约的时间段在删除时保留, 系列也随之保留
+        let change = delete_series_with_db(series, &db).await.unwrap();
+        assert_eq!(change.affected.len(), 5);
+        assert_eq!(change.skipped, vec![booked.id]);
+        let series = time_range_series::Model::find_by_id_with_db(generated.series.id, &db)
+            .await
+            .unwrap();
+
+        experiment_time_ranges::Model::find_by_id_with_db(booked.id, &db)
+            .await
+            .unwrap()
+            .cancel_reservation_with_db(alice.id, &db)
+            .await
+            .unwrap();
+        let change = delete_series_with_db(series, &db).await.unwrap();
+        assert_eq!(change.affected, vec![booked.id]);
+        assert!(matches!(
+            time_range_series::Model::find_by_id_with_db(generated.series.id, &db).await,
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_skips_time_ranges_below_reserved() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let tz = config.timezone();
+        let alice = StudentBuilder::new().insert(&db).await;
+        let bob = StudentBuilder::new().insert(&db).await;
+        let experiment = ExperimentBuilder::new()
+            .student(&alice)
+            .student(&bob)
+            .insert(&db)
+            .await;
+        let generated = generate_series_with_db(experiment.id, rule(tz), &config, &db)
+            .await
+            .unwrap();
+        let booked = &generated.time_ranges[0];
+        for student in [&alice, &bob] {
+            experiment_time_ranges::Model::find_by_id_with_db(booked.id, &db)
+                .await
+                .unwrap()
+                .reserve_with_db(student.id, &config, &db)
+                .await
+                .unwrap();
+        }
+
+        let change = update_series_with_db(
+            &generated.series,
+            SeriesUpdate {
+                capacity: Some(1),
+                ..Default::default()
+            },
+            tz,
+            &db,
+        )
+        .await
+        .unwrap();
+        assert_eq!(change.skipped, vec![booked.id]);
+        assert_eq!(change.affected.len(), 5);
+        let booked = experiment_time_ranges::Model::find_by_id_with_db(booked.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(booked.capacity, config.default_capacity);
+        assert_eq!(booked.reserved, 2);
+        let other = experiment_time_ranges::Model::find_by_id_with_db(change.affected[0], &db)
+            .await
+            .unwrap();
+        assert_eq!(other.capacity, 1);
+        // 部分时间段被跳过时不修改系列的规则
+        let series = time_range_series::Model::find_by_id_with_db(generated.series.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(series.rule().unwrap().capacity, None);
+    }
+
+    #[tokio::test]
+    async fn test_generate_skips_blackout_dates() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let experiment = ExperimentBuilder::new().insert(&db).await;
+        let rule = rule(config.timezone());
+        // 第一周整周放假
+        blackout_date::ActiveModel::new(
+            rule.start_date,
+            rule.start_date + Duration::days(6),
+            "运动会".to_string(),
+        )
+        .unwrap()
+        .insert(&db)
+        .await
+        .unwrap();
+
+        let generated = generate_series_with_db(experiment.id, rule.clone(), &config, &db)
+            .await
+            .unwrap();
+        assert_eq!(generated.time_ranges.len(), 3);
+        let first_week_end = local_to_utc(
+            rule.start_date + Duration::days(7),
+            NaiveTime::MIN,
+            config.timezone(),
+        );
+        assert!(generated
+            .time_ranges
+            .iter()
+            .all(|time_range| time_range.start_time >= first_week_end));
+    }
+
+    #[tokio::test]
+    async fn test_generate_rejects_past_rule() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let experiment = ExperimentBuilder::new().insert(&db).await;
+        let mut past = rule(config.timezone());
+        past.start_date -= Duration::days(60);
+        past.end_date -= Duration::days(60);
+        assert!(matches!(
+            generate_series_with_db(experiment.id, past, &config, &db).await,
+            Err(Error::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_raising_capacity_promotes_waitlist() {
+        let db = test_db().await;
+        let config = ReservationConfig::default();
+        let tz = config.timezone();
+        let students = [
+            StudentBuilder::new().insert(&db).await,
+            StudentBuilder::new().insert(&db).await,
+            StudentBuilder::new().insert(&db).await,
+            StudentBuilder::new().insert(&db).await,
+        ];
+        let mut experiment = ExperimentBuilder::new();
+        for student in &students {
+            experiment = experiment.student(student);
+        }
+        let experiment = experiment.insert(&db).await;
+        let generated = generate_series_with_db(
+            experiment.id,
+            RecurrenceRule {
+                capacity: Some(1),
+                ..rule(tz)
+            },
+            &config,
+            &db,
+        )
+        .await
+        .unwrap();
+        let full = &generated.time_ranges[0];
+        full.reserve_with_db(students[0].id, &config, &db)
+            .await
+            .unwrap();
+        let full = experiment_time_ranges::Model::find_by_id_with_db(full.id, &db)
+            .await
+            .unwrap();
+        for student in &students[1..] {
+            experiment_time_ranges_waitlist::Model::join_with_db(&full, student.id, &db)
+                .await
+                .unwrap();
+        }
+
+        // 人数上限从1调到3, 空出的两个名额按排队顺序递补
+        update_series_with_db(
+            &generated.series,
+            SeriesUpdate {
+                capacity: Some(3),
+                ..Default::default()
+            },
+            tz,
+            &db,
+        )
+        .await
+        .unwrap();
+        let full = experiment_time_ranges::Model::find_by_id_with_db(full.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(full.reserved, 3);
+        let promoted = experiment_time_ranges_waitlist::Model::find_unacknowledged_with_db(
+            students[1].id,
+            &db,
+        )
+        .await
+        .unwrap();
+        assert_eq!(promoted.len(), 1);
+        assert_eq!(
+            experiment_time_ranges_waitlist::Model::find_unacknowledged_with_db(
+                students[3].id,
+                &db
+            )
+            .await
+            .unwrap(),
+            vec![]
+        );
+    }
+}
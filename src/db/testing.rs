@@ -0,0 +1,311 @@
+// 测试用的数据库和数据构造工具
+//
+// 每个测试通过test_db()获得独立的sqlite内存数据库, 互不影响, 可以并行运行.
+// 需要多个连接并发访问的测试使用shared_test_db(), 数据保存在临时文件中.
+
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{ActiveModelTrait, ConnectOptions, Database, DatabaseConnection, Set};
+use sea_orm_migration::MigratorTrait;
+
+use super::{
+    migrations::Migrator,
+    models::{
+        class, class_student_junction, class_teacher_junction, experiment,
+        experiment_student_junction, experiment_teacher_junction, experiment_time_ranges, student,
+        teacher::{self, TeacherRole},
+    },
+};
+
+// 为学号/工号等唯一字段生成不重复的值
+fn next_seq() -> u64 {
+    static SEQ: AtomicU64 = AtomicU64::new(1);
+    SEQ.fetch_add(1, Ordering::Relaxed)
+}
+
+pub async fn test_db() -> DatabaseConnection {
+    let mut options = ConnectOptions::new("sqlite::memory:");
+    // 内存数据库只存在于单个连接中, 连接池只能保留一个连接
+    options
+        .max_connections(1)
+        .min_connections(1)
+        .sqlx_logging(false);
+    let db = Database::connect(options)
+        .await
+        .expect("connect sqlite memory db");
+    Migrator::up(&db, None).await.expect("run migrations");
+    db
+}
+
+// 多个连接共享的sqlite文件数据库, 离开作用域时删除文件
+pub struct SharedTestDb {
+    pub db: DatabaseConnection,
+    path: PathBuf,
+}
+
+impl Drop for SharedTestDb {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+pub async fn shared_test_db(connections: u32) -> SharedTestDb {
+    let path = std::env::temp_dir().join(format!(
+        "fpga_reserve_test_{}_{}.db",
+        std::process::id(),
+        next_seq()
+    ));
+    let mut options = ConnectOptions::new(format!("sqlite://{}?mode=rwc", path.display()));
+    options
+        .max_connections(connections)
+        .min_connections(connections)
+        .sqlx_logging(false);
+    let db = Database::connect(options)
+        .await
+        .expect("connect sqlite file db");
+    Migrator::up(&db, None).await.expect("run migrations");
+    SharedTestDb { db, path }
+}
+
+pub struct StudentBuilder {
+    student_id: Option<String>,
+    account: Option<String>,
+    password: String,
+    name: Option<String>,
+}
+
+impl Default for StudentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StudentBuilder {
+    pub fn new() -> Self {
+        let seq = next_seq();
+        Self {
+            student_id: Some(format!("S{:06}", seq)),
+            account: Some(format!("student{}", seq)),
+            password: "password".to_string(),
+            name: Some(format!("学生{}", seq)),
+        }
+    }
+
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.password = password.into();
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub async fn insert(self, db: &DatabaseConnection) -> student::Model {
+        student::ActiveModel::new_encrypted(self.student_id, self.account, self.password, self.name)
+            .await
+            .expect("hash student password")
+            .insert(db)
+            .await
+            .expect("insert student")
+    }
+}
+
+pub struct TeacherBuilder {
+    teacher_id: Option<String>,
+    account: Option<String>,
+    password: String,
+    name: Option<String>,
+    role: TeacherRole,
+}
+
+impl Default for TeacherBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TeacherBuilder {
+    pub fn new() -> Self {
+        let seq = next_seq();
+        Self {
+            teacher_id: Some(format!("T{:06}", seq)),
+            account: Some(format!("teacher{}", seq)),
+            password: "password".to_string(),
+            name: Some(format!("教师{}", seq)),
+            role: TeacherRole::Teacher,
+        }
+    }
+
+    pub async fn insert(self, db: &DatabaseConnection) -> teacher::Model {
+        let mut active = teacher::ActiveModel::new_encrypted(
+            self.teacher_id,
+            self.account,
+            self.password,
+            self.name,
+        )
+        .await
+        .expect("hash teacher password");
+        active.role = Set(self.role);
+        active.insert(db).await.expect("insert teacher")
+    }
+}
+
+pub struct ClassBuilder {
+    class_id: Option<String>,
+    students: Vec<i64>,
+    teachers: Vec<(i64, bool)>,
+}
+
+impl Default for ClassBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClassBuilder {
+    pub fn new() -> Self {
+        Self {
+            class_id: Some(format!("班级{}", next_seq())),
+            students: Vec::new(),
+            teachers: Vec::new(),
+        }
+    }
+
+    pub fn student(mut self, student: &student::Model) -> Self {
+        self.students.push(student.id);
+        self
+    }
+
+    pub fn teacher(mut self, teacher: &teacher::Model, admin: bool) -> Self {
+        self.teachers.push((teacher.id, admin));
+        self
+    }
+
+    pub async fn insert(self, db: &DatabaseConnection) -> class::Model {
+        let class = class::ActiveModel::new(self.class_id)
+            .insert(db)
+            .await
+            .expect("insert class");
+        for student_pid in self.students {
+            class_student_junction::ActiveModel::new(class.id, student_pid)
+                .insert(db)
+                .await
+                .expect("join class student");
+        }
+        for (teacher_pid, admin) in self.teachers {
+            class_teacher_junction::ActiveModel::new(class.id, teacher_pid, admin)
+                .insert(db)
+                .await
+                .expect("join class teacher");
+        }
+        class
+    }
+}
+
+pub struct ExperimentBuilder {
+    title: String,
+    students: Vec<i64>,
+    teachers: Vec<i64>,
+}
+
+impl Default for ExperimentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExperimentBuilder {
+    pub fn new() -> Self {
+        Self {
+            title: format!("实验{}", next_seq()),
+            students: Vec::new(),
+            teachers: Vec::new(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    pub fn student(mut self, student: &student::Model) -> Self {
+        self.students.push(student.id);
+        self
+    }
+
+    // 负责教师
+    pub fn teacher(mut self, teacher: &teacher::Model) -> Self {
+        self.teachers.push(teacher.id);
+        self
+    }
+
+    pub async fn insert(self, db: &DatabaseConnection) -> experiment::Model {
+        let experiment = experiment::ActiveModel::new(self.title)
+            .insert(db)
+            .await
+            .expect("insert experiment");
+        for student_pid in self.students {
+            experiment_student_junction::ActiveModel::new(experiment.id, student_pid)
+                .insert(db)
+                .await
+                .expect("enroll experiment student");
+        }
+        for teacher_pid in self.teachers {
+            experiment_teacher_junction::ActiveModel::new(experiment.id, teacher_pid)
+                .insert(db)
+                .await
+                .expect("add experiment teacher");
+        }
+        experiment
+    }
+}
+
+pub struct TimeRangeBuilder {
+    experiment_pid: i64,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    capacity: i32,
+}
+
+impl TimeRangeBuilder {
+    // 默认为明天的两小时时段
+    pub fn new(experiment: &experiment::Model) -> Self {
+        let start_time = Utc::now() + Duration::days(1);
+        Self {
+            experiment_pid: experiment.id,
+            start_time,
+            end_time: start_time + Duration::hours(2),
+            capacity: 10,
+        }
+    }
+
+    pub fn start_time(mut self, start_time: DateTime<Utc>) -> Self {
+        let length = self.end_time - self.start_time;
+        self.start_time = start_time;
+        self.end_time = start_time + length;
+        self
+    }
+
+    pub fn capacity(mut self, capacity: i32) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub async fn insert(self, db: &DatabaseConnection) -> experiment_time_ranges::Model {
+        experiment_time_ranges::ActiveModel::new(
+            self.experiment_pid,
+            self.start_time,
+            self.end_time,
+            self.capacity,
+        )
+        .expect("valid time range")
+        .insert(db)
+        .await
+        .expect("insert time range")
+    }
+}
@@ -0,0 +1,177 @@
+// 访问令牌(JWT)与刷新令牌的签发和校验
+
+use chrono::Utc;
+use jsonwebtoken::{Header, Validation};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{Error, Result},
+    keys::KeySet,
+};
+
+// 访问令牌默认有效期: 15分钟
+pub const ACCESS_TOKEN_LIFETIME_SECS: i64 = 15 * 60;
+// 刷新令牌默认有效期: 30天
+pub const REFRESH_TOKEN_LIFETIME_SECS: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    // 学生或教师的主键
+    pub sub: i64,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    // 为true时需要先修改密码才能使用其他接口
+    pub must_change_password: bool,
+}
+
+impl TokenPair {
+    pub fn new(access_token: String, refresh_token: String, expires_in: i64) -> Self {
+        Self {
+            access_token,
+            refresh_token,
+            token_type: "Bearer".to_string(),
+            expires_in,
+            must_change_password: false,
+        }
+    }
+}
+
+// 使用当前签名密钥签发, 并在头部写入kid
+pub fn encode_access_token(sub: i64, keys: &KeySet, lifetime_secs: i64) -> Result<String> {
+    let now = Utc::now().timestamp();
+    let claims = Claims {
+        sub,
+        iat: now,
+        exp: now + lifetime_secs,
+    };
+    let mut header = Header::new(keys.active_algorithm());
+    header.kid = Some(keys.active_kid().to_string());
+    jsonwebtoken::encode(&header, &claims, keys.encoding_key()).map_err(Error::internal)
+}
+
+// 根据头部的kid选择校验密钥, 只接受该密钥对应的算法
+pub fn decode_access_token(token: &str, keys: &KeySet) -> Result<Claims> {
+    let header = jsonwebtoken::decode_header(token)
+        .map_err(|e| Error::Unauthorized(format!("Invalid access token: {}", e)))?;
+    let (algorithm, key) = keys
+        .decoding_key(header.kid.as_deref())
+        .ok_or(Error::Unauthorized("Unknown signing key".to_string()))?;
+    jsonwebtoken::decode::<Claims>(token, key, &Validation::new(algorithm))
+        .map(|data| data.claims)
+        .map_err(|e| Error::Unauthorized(format!("Invalid access token: {}", e)))
+}
+
+// 生成256位随机刷新令牌
+pub fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+pub fn hash_refresh_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use jsonwebtoken::Algorithm;
+
+    fn key_set(keys: &[(&str, &[u8])]) -> KeySet {
+        KeySet::from_secrets(
+            keys.iter()
+                .map(|(kid, secret)| (kid.to_string(), secret.to_vec()))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_access_token_round_trip() {
+        let keys = key_set(&[("k1", b"test-secret")]);
+        let token = encode_access_token(42, &keys, ACCESS_TOKEN_LIFETIME_SECS).unwrap();
+        let claims = decode_access_token(&token, &keys).unwrap();
+        assert_eq!(claims.sub, 42);
+        assert!(decode_access_token(&token, &key_set(&[("k1", b"other")])).is_err());
+    }
+
+    #[test]
+    fn test_access_token_after_key_rotation() {
+        let old_keys = key_set(&[("k1", b"old-secret")]);
+        let token = encode_access_token(7, &old_keys, ACCESS_TOKEN_LIFETIME_SECS).unwrap();
+
+        // 新密钥签名, 旧密钥仍可校验
+        let rotated = key_set(&[("k2", b"new-secret"), ("k1", b"old-secret")]);
+        assert_eq!(decode_access_token(&token, &rotated).unwrap().sub, 7);
+        let new_token = encode_access_token(7, &rotated, ACCESS_TOKEN_LIFETIME_SECS).unwrap();
+        assert_eq!(
+            jsonwebtoken::decode_header(&new_token)
+                .unwrap()
+                .kid
+                .as_deref(),
+            Some("k2")
+        );
+
+        // 旧密钥下线后旧令牌失效
+        let retired = key_set(&[("k2", b"new-secret")]);
+        assert!(decode_access_token(&token, &retired).is_err());
+    }
+
+    #[test]
+    fn test_eddsa_access_token() {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = ring::signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let keys = KeySet::from_keys(vec![(
+            "ed".to_string(),
+            Algorithm::EdDSA,
+            pkcs8.as_ref().to_vec(),
+        )])
+        .unwrap();
+        let token = encode_access_token(3, &keys, ACCESS_TOKEN_LIFETIME_SECS).unwrap();
+        assert_eq!(
+            jsonwebtoken::decode_header(&token).unwrap().alg,
+            Algorithm::EdDSA
+        );
+        assert_eq!(decode_access_token(&token, &keys).unwrap().sub, 3);
+    }
+
+    #[test]
+    fn test_rs256_access_token() {
+        let pkcs8 = include_bytes!("../../test_data/rsa_test_key.pkcs8.der");
+        let keys = KeySet::from_keys(vec![
+            ("rsa".to_string(), Algorithm::RS256, pkcs8.to_vec()),
+            ("hs".to_string(), Algorithm::HS256, b"secret".to_vec()),
+        ])
+        .unwrap();
+        let token = encode_access_token(5, &keys, ACCESS_TOKEN_LIFETIME_SECS).unwrap();
+        assert_eq!(
+            jsonwebtoken::decode_header(&token).unwrap().alg,
+            Algorithm::RS256
+        );
+        assert_eq!(decode_access_token(&token, &keys).unwrap().sub, 5);
+        assert_eq!(keys.jwks().keys.len(), 1);
+
+        // 用HS256密钥签名但声明为RSA密钥的令牌会被拒绝
+        let hs_keys = key_set(&[("rsa", b"secret")]);
+        let forged = encode_access_token(5, &hs_keys, ACCESS_TOKEN_LIFETIME_SECS).unwrap();
+        assert!(decode_access_token(&forged, &keys).is_err());
+    }
+
+    #[test]
+    fn test_refresh_token_hash() {
+        let token = generate_refresh_token();
+        assert_eq!(token.len(), 64);
+        assert_eq!(hash_refresh_token(&token), hash_refresh_token(&token));
+        assert_ne!(hash_refresh_token(&token), token);
+    }
+}
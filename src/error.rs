@@ -0,0 +1,158 @@
+// 全局错误类型, 每个变体对应一个HTTP状态码和稳定的错误码
+
+use axum::{
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::{DbErr, SqlErr};
+use serde::{Deserialize, Serialize};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Invalid account or password")]
+    InvalidCredentials,
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    #[error("Password must be changed before using other APIs")]
+    PasswordChangeRequired,
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Too many failed attempts, retry after {retry_after_secs} seconds")]
+    TooManyAttempts { retry_after_secs: i64 },
+    #[error("Database error: {0}")]
+    Database(DbErr),
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl Error {
+    pub fn not_found(what: impl Into<String>) -> Self {
+        Error::NotFound(what.into())
+    }
+
+    pub fn internal(e: impl std::fmt::Debug) -> Self {
+        Error::Internal(format!("{:?}", e))
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::Conflict(_) => StatusCode::CONFLICT,
+            Error::InvalidCredentials | Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Error::Forbidden(_) | Error::PasswordChangeRequired => StatusCode::FORBIDDEN,
+            Error::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Error::TooManyAttempts { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Error::Database(_) | Error::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // 供客户端判断错误类型, 不随错误信息变化
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::NotFound(_) => "not_found",
+            Error::Conflict(_) => "conflict",
+            Error::InvalidCredentials => "invalid_credentials",
+            Error::Unauthorized(_) => "unauthorized",
+            Error::Forbidden(_) => "forbidden",
+            Error::PasswordChangeRequired => "password_change_required",
+            Error::BadRequest(_) => "bad_request",
+            Error::TooManyAttempts { .. } => "too_many_attempts",
+            Error::Database(_) => "database_error",
+            Error::Internal(_) => "internal_error",
+        }
+    }
+}
+
+// 唯一约束和外键约束冲突属于请求本身的问题, 其余数据库错误视为服务端错误
+impl From<DbErr> for Error {
+    fn from(e: DbErr) -> Self {
+        match e.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(msg)) => Error::Conflict(msg),
+            Some(SqlErr::ForeignKeyConstraintViolation(msg)) => Error::Conflict(msg),
+            _ => Error::Database(e),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        // 不向客户端暴露内部错误细节
+        let message = match &self {
+            Error::Database(_) | Error::Internal(_) => {
+                tracing::error!("{}", self);
+                status
+                    .canonical_reason()
+                    .unwrap_or("Internal Server Error")
+                    .to_string()
+            }
+            _ => self.to_string(),
+        };
+        let body = ErrorBody {
+            code: self.code().to_string(),
+            message,
+        };
+        let mut response = (status, Json(body)).into_response();
+        if let Error::TooManyAttempts { retry_after_secs } = self {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs.max(1)));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_status_and_code() {
+        let cases = [
+            (
+                Error::not_found("Student"),
+                StatusCode::NOT_FOUND,
+                "not_found",
+            ),
+            (
+                Error::Conflict("dup".to_string()),
+                StatusCode::CONFLICT,
+                "conflict",
+            ),
+            (
+                Error::InvalidCredentials,
+                StatusCode::UNAUTHORIZED,
+                "invalid_credentials",
+            ),
+            (
+                Error::Database(DbErr::Custom("down".to_string())),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "database_error",
+            ),
+            (
+                Error::internal("boom"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+            ),
+        ];
+        for (error, status, code) in cases {
+            assert_eq!(error.status(), status);
+            assert_eq!(error.code(), code);
+        }
+    }
+}
@@ -1,3 +1,109 @@
-fn main() {
-    println!("Hello, world!");
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use axum::Router;
+use fpga_reserve::{
+    config::Config,
+    db::{
+        api,
+        db_conn::{connect, init_db},
+        init_argon2,
+        models::teacher,
+    },
+    keys::JwtKeys,
+    state::AppState,
+};
+use tokio::net::TcpListener;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let config = Config::load()?;
+    init_argon2(&config.argon2)?;
+    let keys = JwtKeys::from_config(&config.jwt)?;
+    let db = connect(&config.database).await?;
+    init_db(&db).await?;
+
+    // 首次部署时通过环境变量创建系统管理员账号
+    if let (Ok(account), Ok(password)) = (
+        std::env::var("FPGA_RESERVE_ADMIN_ACCOUNT"),
+        std::env::var("FPGA_RESERVE_ADMIN_PASSWORD"),
+    ) {
+        teacher::Model::ensure_system_admin_with_db(account, password, &db).await?;
+    }
+
+    let listen_addr = config.server.listen_addr.clone();
+    let cors = cors_layer(&config.server.cors_origins)?;
+    let state = AppState::new(db, keys, config);
+    let mut app = Router::new()
+        .nest("/api/v1", api::router())
+        .with_state(state.clone());
+    if let Some(cors) = cors {
+        app = app.layer(cors);
+    }
+
+    let listener = TcpListener::bind(&listen_addr).await?;
+    tracing::info!("Listening on {}", listener.local_addr()?);
+    // 登录失败限制需要客户端地址
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+
+    state.db.close().await?;
+    tracing::info!("Server stopped");
+    Ok(())
+}
+
+// 未配置来源时不启用跨域
+fn cors_layer(origins: &[String]) -> Result<Option<CorsLayer>> {
+    if origins.is_empty() {
+        return Ok(None);
+    }
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            origins
+                .iter()
+                .map(|origin| origin.parse())
+                .collect::<Result<Vec<_>, _>>()?,
+        )
+    };
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(Any)
+            .allow_headers(Any),
+    ))
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutdown signal received");
 }
@@ -0,0 +1,24 @@
+// 所有请求处理函数共享的应用状态, 由main创建后注入到路由中
+
+use std::sync::Arc;
+
+use sea_orm::DatabaseConnection;
+
+use crate::{config::Config, keys::JwtKeys};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub db: DatabaseConnection,
+    pub keys: Arc<JwtKeys>,
+    pub config: Arc<Config>,
+}
+
+impl AppState {
+    pub fn new(db: DatabaseConnection, keys: JwtKeys, config: Config) -> Self {
+        Self {
+            db,
+            keys: Arc::new(keys),
+            config: Arc::new(config),
+        }
+    }
+}